@@ -0,0 +1,416 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+
+use crate::SUB_MATRIX_CHUNK_SIZE;
+use crate::classification::Classification;
+use crate::classification::Classifier;
+use crate::engine::BoundingBox;
+use crate::engine::Engine;
+use crate::rle::Pattern;
+use crate::rule::Rule;
+
+
+const ISOLATION_MARGIN: u32 = 64; // Empty cells around an object while it's run on its own
+const MAX_OBJECT_GENERATIONS: i32 = 1024; // Objects that don't repeat within this amount of generations are reported as unknown
+pub const UNKNOWN_APGCODE: &str = "zz_UNKNOWN";
+
+const WECHSLER_DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+type Cell = (i64, i64);
+type StateCell = (Cell, u8); // A cell and its state, 1 for alive cells in 2 state rules
+
+const KNOWN_OBJECTS: &[(&str, &str)] = &[
+	("xs4_33", "block"),
+	("xs4_252", "tub"),
+	("xs5_253", "boat"),
+	("xs6_356", "ship"),
+	("xs6_696", "beehive"),
+	("xs6_25a4", "barge"),
+	("xs7_2596", "loaf"),
+	("xs7_25ac", "long boat"),
+	("xs8_6996", "pond"),
+	("xp2_7", "blinker"),
+	("xp2_7e", "toad"),
+	("xp2_318c", "beacon"),
+	("xp3_co9nas0san9oczgoldlo0oldlogz1047210127401", "pulsar"),
+	("xp15_4r4z4r4", "pentadecathlon"),
+	("xq4_153", "glider"),
+	("xq4_6frc", "lightweight spaceship"),
+	("xq4_27dee6", "middleweight spaceship"),
+	("xq4_27deee6", "heavyweight spaceship")
+];
+
+
+pub fn known_name(apgcode: &str) -> Option<&'static str> {
+	KNOWN_OBJECTS.iter().find(|(code, _)| *code == apgcode).map(|(_, name)| *name)
+}
+
+// Splits the cells that aren't dead, given as (row, col, state), into groups of cells touching each other (including
+// diagonally). Dying cells are part of the objects they trail behind
+pub fn separate(cells: &[(u32, u32, u8)]) -> Vec<Vec<(u32, u32, u8)>> {
+	let mut remaining: HashMap<(u32, u32), u8> = cells.iter().map(|(row, col, state)| ((*row, *col), *state)).collect();
+	let mut objects = Vec::new();
+
+	for (row, col, state) in cells {
+		if remaining.remove(&(*row, *col)).is_none() {
+			continue;
+		}
+
+		let mut object = vec![(*row, *col, *state)];
+		let mut pending = vec![(*row, *col)];
+		while let Some((row, col)) = pending.pop() {
+			for neighbor_row in row.saturating_sub(1)..=row + 1 {
+				for neighbor_col in col.saturating_sub(1)..=col + 1 {
+					if let Some(state) = remaining.remove(&(neighbor_row, neighbor_col)) {
+						object.push((neighbor_row, neighbor_col, state));
+						pending.push((neighbor_row, neighbor_col));
+					}
+				}
+			}
+		}
+		objects.push(object);
+	}
+
+	objects
+}
+
+// Moves the cells of an object so their bounding box starts at (0, 0), sorted so equal objects compare equal
+pub fn normalize(cells: &[(u32, u32, u8)]) -> Vec<(u32, u32, u8)> {
+	let min_row = cells.iter().map(|cell| cell.0).min().unwrap_or(0);
+	let min_col = cells.iter().map(|cell| cell.1).min().unwrap_or(0);
+	let mut normalized: Vec<(u32, u32, u8)> = cells.iter().map(|(row, col, state)| (row - min_row, col - min_col, *state)).collect();
+	normalized.sort();
+	normalized
+}
+
+// Same as normalize, for the cells of the transformed phases, which can be negative
+fn normalize_states(cells: &[StateCell]) -> Vec<StateCell> {
+	let min_row = cells.iter().map(|((row, _), _)| *row).min().unwrap_or(0);
+	let min_col = cells.iter().map(|((_, col), _)| *col).min().unwrap_or(0);
+	let mut normalized: Vec<StateCell> = cells.iter().map(|((row, col), state)| ((row - min_row, col - min_col), *state)).collect();
+	normalized.sort();
+	normalized
+}
+
+fn push_zeros(code: &mut String, mut amount: usize) {
+	while amount > 0 {
+		match amount {
+			1 => { code.push('0'); amount = 0; },
+			2 => { code.push('w'); amount = 0; },
+			3 => { code.push('x'); amount = 0; },
+			_ => {
+				let run = usize::min(amount, 39);
+				code.push('y');
+				code.push(WECHSLER_DIGITS[run - 4] as char);
+				amount -= run;
+			}
+		}
+	}
+}
+
+// Extended Wechsler format: strips of 5 rows, each column encoded as a 5 bit digit and runs of empty columns compressed
+fn wechsler(cells: &[Cell]) -> String {
+	let height = cells.iter().map(|cell| cell.0 + 1).max().unwrap_or(0) as usize;
+	let width = cells.iter().map(|cell| cell.1 + 1).max().unwrap_or(0) as usize;
+	let alive: HashSet<Cell> = cells.iter().copied().collect();
+
+	let mut code = String::new();
+	for strip in 0..height.div_ceil(5) {
+		if strip > 0 {
+			code.push('z');
+		}
+
+		let mut columns: Vec<usize> = (0..width).map(|col| {
+			(0..5).filter(|bit| alive.contains(&((strip * 5 + bit) as i64, col as i64))).map(|bit| 1 << bit).sum()
+		}).collect();
+		while columns.last() == Some(&0) {
+			columns.pop();
+		}
+
+		let mut zeros = 0;
+		for column in columns {
+			if column == 0 {
+				zeros += 1;
+				continue;
+			}
+			push_zeros(&mut code, zeros);
+			zeros = 0;
+			code.push(WECHSLER_DIGITS[column] as char);
+		}
+	}
+	code
+}
+
+// Each state in Extended Wechsler format, separated by underscores. Cells of 2 state rules are all in state 1, so they get a
+// single one
+fn layers(cells: &[StateCell]) -> String {
+	let highest = cells.iter().map(|(_, state)| *state).max().unwrap_or(1);
+	(1..=highest).map(|layer| {
+		let layer_cells: Vec<Cell> = cells.iter().filter(|(_, state)| *state == layer).map(|(cell, _)| *cell).collect();
+		wechsler(&layer_cells)
+	}).collect::<Vec<String>>().join("_")
+}
+
+// The shortest (then alphabetically first) representation among every phase, rotation and reflection
+fn canonical_code(phases: &[Vec<StateCell>]) -> String {
+	let transformations: [fn(Cell) -> Cell; 8] = [
+		|(row, col)| (row, col),
+		|(row, col)| (row, -col),
+		|(row, col)| (-row, col),
+		|(row, col)| (-row, -col),
+		|(row, col)| (col, row),
+		|(row, col)| (col, -row),
+		|(row, col)| (-col, row),
+		|(row, col)| (-col, -row)
+	];
+
+	phases.iter()
+		.flat_map(|phase| transformations.iter().map(move |transformation| {
+			let transformed: Vec<StateCell> = phase.iter().map(|(cell, state)| (transformation(*cell), *state)).collect();
+			layers(&normalize_states(&transformed))
+		}))
+		.min_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)))
+		.unwrap_or_default()
+}
+
+// Cells of the isolated object and their states
+fn phase(engine: &Engine) -> Vec<StateCell> {
+	engine.state_cells(None).into_iter().map(|(row, col, state)| ((row as i64, col as i64), state)).collect()
+}
+
+// The object as a pattern, its alive cells apart from the others like in RLE files
+fn to_pattern(cells: &[(u32, u32, u8)]) -> Pattern {
+	let normalized = normalize(cells);
+	Pattern {
+		width: normalized.iter().map(|cell| cell.1 + 1).max().unwrap_or(0),
+		height: normalized.iter().map(|cell| cell.0 + 1).max().unwrap_or(0),
+		cells: normalized.iter().filter(|cell| cell.2 == 1).map(|(row, col, _)| (*row, *col)).collect(),
+		states: normalized.iter().filter(|cell| cell.2 != 1).copied().collect(),
+		rule: None
+	}
+}
+
+// Runs the object, given as (row, col, state), on its own until it repeats, and builds its apgcode (e.g. xs4_33 for a block,
+// xq4_153 for a glider). In rules with more than 2 states each state is encoded on its own
+pub fn apgcode(cells: &[(u32, u32, u8)], rule: &Rule) -> String {
+	let pattern = to_pattern(cells);
+
+	let size = (u32::max(pattern.width, pattern.height) + ISOLATION_MARGIN * 2).div_ceil(SUB_MATRIX_CHUNK_SIZE) * SUB_MATRIX_CHUNK_SIZE;
+	let mut engine = Engine::with_size(size);
+	engine.set_rule(rule.clone());
+	if engine.load_pattern(&pattern, (size - pattern.height) / 2, (size - pattern.width) / 2).is_err() {
+		return UNKNOWN_APGCODE.to_string();
+	}
+
+	let mut classifier = Classifier::new(None);
+	let mut phases = vec![phase(&engine)];
+	let mut classification = classifier.observe(&engine);
+	while classification.is_none() && engine.generation_number() < MAX_OBJECT_GENERATIONS {
+		engine.iterate();
+		phases.push(phase(&engine));
+		classification = classifier.observe(&engine);
+	}
+
+	let (prefix, start, period) = match classification {
+		Some((Classification::StillLife, start)) => (format!("xs{}", phases[start as usize].iter().filter(|(_, state)| rule.alive(*state)).count()), start, 1),
+		Some((Classification::Oscillator { period }, start)) => (format!("xp{}", period), start, period),
+		Some((Classification::Spaceship { period, .. }, start)) => (format!("xq{}", period), start, period),
+		_ => return UNKNOWN_APGCODE.to_string()
+	};
+
+	format!("{}_{}", prefix, canonical_code(&phases[start as usize..(start as usize + period as usize)]))
+}
+
+
+pub struct CensusEntry {
+	pub apgcode: String,
+	pub name: Option<&'static str>,
+	pub count: u32,
+	pub sample: Vec<(u32, u32, u8)> // Cells of one of the occurrences and their states, relative to its top left corner
+}
+
+impl CensusEntry {
+	pub fn sample_pattern(&self) -> Pattern {
+		to_pattern(&self.sample)
+	}
+}
+
+// Tally of the objects a pattern is made of, most common first
+pub struct Census {
+	pub entries: Vec<CensusEntry>,
+	rule: Rule // Objects only get the names of the catalogue in B3/S23
+}
+
+impl Census {
+	pub fn new(rule: Rule) -> Self {
+		Self {
+			entries: Vec::new(),
+			rule
+		}
+	}
+
+	// Only the cells inside the region are taken into account, or the whole matrix if there is no region
+	pub fn take(engine: &Engine, region: Option<BoundingBox>) -> Self {
+		Census::from_objects(separate(&engine.state_cells(region)), engine.rule().clone())
+	}
+
+	// Runs the engine for a whole period before taking the census, and separates the objects by every cell they occupied
+	// during it. Otherwise the parts of some oscillators look like separate objects in some of their phases
+	pub fn take_periodic(engine: &mut Engine, period: u32) -> Self {
+		let mut occupied: HashSet<(u32, u32, u8)> = engine.state_cells(None).into_iter().map(|(row, col, _)| (row, col, 1)).collect();
+		for _ in 0..period {
+			engine.iterate();
+			occupied.extend(engine.state_cells(None).into_iter().map(|(row, col, _)| (row, col, 1)));
+		}
+
+		let mut areas: HashMap<(u32, u32), usize> = HashMap::new();
+		for (index, area) in separate(&occupied.into_iter().collect::<Vec<(u32, u32, u8)>>()).into_iter().enumerate() {
+			areas.extend(area.into_iter().map(|(row, col, _)| ((row, col), index)));
+		}
+
+		let mut objects: HashMap<usize, Vec<(u32, u32, u8)>> = HashMap::new();
+		for (row, col, state) in engine.state_cells(None) {
+			objects.entry(areas[&(row, col)]).or_default().push((row, col, state));
+		}
+		Census::from_objects(objects.into_values().collect(), engine.rule().clone())
+	}
+
+	fn from_objects(objects: Vec<Vec<(u32, u32, u8)>>, rule: Rule) -> Self {
+		let mut identified: HashMap<Vec<(u32, u32, u8)>, String> = HashMap::new();
+		let mut census = Census::new(rule.clone());
+
+		for object in objects {
+			// Equal objects (in the same phase and orientation) are only run once
+			let shape = normalize(&object);
+			let code = identified.entry(shape.clone()).or_insert_with(|| apgcode(&object, &rule)).clone();
+			census.add(code, 1, shape);
+		}
+
+		census.sort();
+		census
+	}
+
+	// Counts an object that was already identified
+	pub fn add_object(&mut self, apgcode: String, cells: &[(u32, u32, u8)]) {
+		self.add(apgcode, 1, normalize(cells));
+		self.sort();
+	}
+
+	// Adds the counts of another census, e.g. to tally several soups together
+	pub fn merge(&mut self, other: &Census) {
+		for entry in other.entries.iter() {
+			self.add(entry.apgcode.clone(), entry.count, entry.sample.clone());
+		}
+		self.sort();
+	}
+
+	fn add(&mut self, apgcode: String, count: u32, sample: Vec<(u32, u32, u8)>) {
+		match self.entries.iter_mut().find(|entry| entry.apgcode == apgcode) {
+			Some(entry) => entry.count += count,
+			None => self.entries.push(CensusEntry {
+				name: if self.rule.is_life() { known_name(apgcode.as_str()) } else { None },
+				apgcode,
+				count,
+				sample
+			})
+		}
+	}
+
+	// Most common first
+	fn sort(&mut self) {
+		self.entries.sort_by(|a, b| b.count.cmp(&a.count).then(a.apgcode.cmp(&b.apgcode)));
+	}
+
+	pub fn print(&self) {
+		println!("Census:");
+		for entry in self.entries.iter() {
+			match entry.name {
+				Some(name) => println!("{:>9} {} ({})", entry.count, entry.apgcode, name),
+				None => println!("{:>9} {}", entry.count, entry.apgcode)
+			}
+		}
+	}
+
+	pub fn to_csv(&self) -> String {
+		let mut csv = "apgcode,name,count\n".to_string();
+		for entry in &self.entries {
+			csv += format!("{},{},{}\n", entry.apgcode, entry.name.unwrap_or(""), entry.count).as_str();
+		}
+		csv
+	}
+
+	pub fn to_json(&self) -> String {
+		let entries: Vec<String> = self.entries.iter().map(|entry| {
+			let name = match entry.name {
+				Some(name) => format!("\"{}\"", name),
+				None => "null".to_string()
+			};
+			format!("\t{{\"apgcode\": \"{}\", \"name\": {}, \"count\": {}}}", entry.apgcode, name, entry.count)
+		}).collect();
+		format!("[\n{}\n]\n", entries.join(",\n"))
+	}
+
+	// The format is chosen from the extension, JSON for .json and CSV otherwise
+	pub fn save(&self, path: &str) -> Result<(), String> {
+		let contents = if path.to_lowercase().ends_with(".json") { self.to_json() } else { self.to_csv() };
+		fs::write(path, contents).map_err(|error| format!("could not write {}: {}", path, error))
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::rle;
+	use crate::rule_table::RuleTable;
+
+	#[test]
+	fn separate_touching_cells() {
+		let objects = separate(&[(0, 0, 1), (1, 1, 1), (5, 5, 1), (5, 6, 1), (0, 9, 1)]);
+		assert_eq!(objects.len(), 3);
+		assert_eq!(objects[0], vec![(0, 0, 1), (1, 1, 1)]);
+	}
+
+	#[test]
+	fn apgcodes_of_common_objects() {
+		let life = Rule::life();
+		assert_eq!(apgcode(&[(0, 0, 1), (0, 1, 1), (1, 0, 1), (1, 1, 1)], &life), "xs4_33");
+		assert_eq!(apgcode(&[(0, 0, 1), (0, 1, 1), (0, 2, 1)], &life), "xp2_7");
+		assert_eq!(apgcode(&[(0, 1, 1), (1, 2, 1), (2, 0, 1), (2, 1, 1), (2, 2, 1)], &life), "xq4_153");
+		assert_eq!(known_name("xs4_33"), Some("block"));
+	}
+
+	#[test]
+	fn canonical_code_ignores_rotations() {
+		let block = vec![((5, 5), 1), ((5, 6), 1), ((6, 5), 1), ((6, 6), 1)];
+		assert_eq!(canonical_code(&[block]), "33");
+		let horizontal = vec![((0, 0), 1), ((0, 1), 1), ((0, 2), 1)];
+		let vertical = vec![((0, 0), 1), ((1, 0), 1), ((2, 0), 1)];
+		assert_eq!(canonical_code(&[horizontal]), canonical_code(&[vertical]));
+	}
+
+	#[test]
+	fn census_keeps_the_states_of_multi_state_oscillators() {
+		// An electron going around a loop of conductor, its head and tail make it an oscillator instead of a still life
+		let rule = Rule::from_table(Arc::new(RuleTable::parse(include_str!("../rules/WireWorld.rule")).unwrap()));
+		let ring = rle::parse("x = 6, y = 3, rule = WireWorld\n.BACC.$C4.C$.4C.!").unwrap();
+		let mut engine = Engine::with_size(SUB_MATRIX_CHUNK_SIZE);
+		engine.set_rule(rule);
+		engine.load_pattern(&ring, 10, 10).unwrap();
+
+		let census = Census::take(&engine, None);
+		assert_eq!(census.entries.len(), 1);
+		assert_eq!(census.entries[0].apgcode, "xp10_g_z01_e1u");
+		let sample = census.entries[0].sample_pattern();
+		assert_eq!((sample.cells, sample.states), (ring.cells, ring.states));
+	}
+
+	#[test]
+	fn canonical_code_encodes_each_state() {
+		assert_eq!(canonical_code(&[vec![((0, 0), 1), ((0, 1), 2)]]), "1_2");
+		assert_eq!(canonical_code(&[vec![((0, 0), 1), ((0, 1), 1)]]), "3");
+	}
+}
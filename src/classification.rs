@@ -0,0 +1,175 @@
+use core::fmt;
+use std::collections::VecDeque;
+
+use crate::engine::BoundingBox;
+use crate::engine::Engine;
+
+
+const MAX_PERIOD: usize = 1024; // How many past generations are kept to look for repetitions
+
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Classification {
+	Extinct,
+	StillLife,
+	Oscillator { period: u32 },
+	Spaceship { period: u32, dx: i64, dy: i64 } // Displacement per period, in cells (positive dx moves right, positive dy moves down)
+}
+
+impl Classification {
+	// Speed in the usual c/period notation, reduced (e.g. the LWSS moves 2 cells every 4 generations, so c/2)
+	pub fn speed(&self) -> Option<String> {
+		let (period, dx, dy) = match self {
+			Classification::Spaceship { period, dx, dy } => (*period as i64, dx.abs(), dy.abs()),
+			_ => return None
+		};
+
+		let displacement = i64::max(dx, dy);
+		let divisor = gcd(displacement, period);
+		let (displacement, period) = (displacement / divisor, period / divisor);
+		let direction = if dx == 0 || dy == 0 {
+			"orthogonal"
+		} else if dx == dy {
+			"diagonal"
+		} else {
+			"oblique"
+		};
+
+		Some(if displacement == 1 {
+			format!("c/{} {}", period, direction)
+		} else {
+			format!("{}c/{} {}", displacement, period, direction)
+		})
+	}
+}
+
+impl fmt::Display for Classification {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Classification::Extinct => write!(f, "Extinct"),
+			Classification::StillLife => write!(f, "Still life"),
+			Classification::Oscillator { period } => write!(f, "Oscillator (p{})", period),
+			Classification::Spaceship { period, dx, dy } => write!(f, "Spaceship (p{}, {}, moves {},{})", period, self.speed().unwrap(), dx, dy)
+		}
+	}
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+	if b == 0 { a } else { gcd(b, a % b) }
+}
+
+
+struct Snapshot {
+	generation: i32,
+	hash: u64,
+	bounding_box: BoundingBox
+}
+
+// Detects when the observed pattern starts repeating itself, either in place or translated
+pub struct Classifier {
+	region: Option<BoundingBox>,
+	history: VecDeque<Snapshot>,
+	classification: Option<(Classification, i32)> // Classification and the generation in which it was first detected
+}
+
+impl Classifier {
+	// Only the cells inside the region are observed, or the whole matrix if there is no region
+	pub fn new(region: Option<BoundingBox>) -> Self {
+		Self {
+			region,
+			history: VecDeque::new(),
+			classification: None
+		}
+	}
+
+	pub fn reset(&mut self, region: Option<BoundingBox>) {
+		self.region = region;
+		self.history.clear();
+		self.classification = None;
+	}
+
+	pub fn classification(&self) -> Option<(Classification, i32)> {
+		self.classification
+	}
+
+	// Hashes the current generation of the engine and compares it with the previous ones.
+	// Must be called once per generation, the result is kept once found
+	pub fn observe(&mut self, engine: &Engine) -> Option<(Classification, i32)> {
+		if self.classification.is_some() {
+			return self.classification;
+		}
+
+		let generation = engine.generation_number();
+		let (hash, bounding_box) = match engine.normalized_hash(self.region) {
+			Some(normalized) => normalized,
+			None => {
+				self.classification = Some((Classification::Extinct, generation));
+				return self.classification;
+			}
+		};
+
+		// The most recent match is the smallest period
+		if let Some(previous) = self.history.iter().rev().find(|snapshot| snapshot.hash == hash && snapshot.bounding_box.width() == bounding_box.width() && snapshot.bounding_box.height() == bounding_box.height()) {
+			let period = (generation - previous.generation) as u32;
+			let dx = bounding_box.left as i64 - previous.bounding_box.left as i64;
+			let dy = bounding_box.top as i64 - previous.bounding_box.top as i64;
+
+			let classification = if dx != 0 || dy != 0 {
+				Classification::Spaceship { period, dx, dy }
+			} else if period == 1 {
+				Classification::StillLife
+			} else {
+				Classification::Oscillator { period }
+			};
+			self.classification = Some((classification, previous.generation));
+			return self.classification;
+		}
+
+		self.history.push_back(Snapshot { generation, hash, bounding_box });
+		if self.history.len() > MAX_PERIOD {
+			self.history.pop_front();
+		}
+		None
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::rle;
+
+	// Runs the Critters glider from a position until it's classified
+	fn classify_critters_glider(top: u32, left: u32) -> Option<Classification> {
+		let glider = rle::parse("x = 4, y = 2, rule = Critters\no2bo$b2o!").unwrap();
+		let mut engine = Engine::with_size(crate::SUB_MATRIX_CHUNK_SIZE);
+		engine.set_rule(glider.parsed_rule().unwrap());
+		engine.load_pattern(&glider, top, left).unwrap();
+
+		let mut classifier = Classifier::new(None);
+		let mut classification = classifier.observe(&engine);
+		while classification.is_none() && engine.generation_number() < 32 {
+			engine.iterate();
+			classification = classifier.observe(&engine);
+		}
+		classification.map(|(classification, _)| classification)
+	}
+
+	#[test]
+	fn critters_glider_is_a_spaceship() {
+		// The blocks of the first generation split the glider in the middle when it starts on an odd row and column
+		assert_eq!(classify_critters_glider(31, 31), Some(Classification::Spaceship { period: 4, dx: 0, dy: 2 }));
+	}
+
+	#[test]
+	fn block_rules_repeat_in_even_periods() {
+		// The grid of blocks moves every generation, the same cells a generation later don't evolve the same way
+		for (top, left) in [(30, 30), (30, 31), (31, 30), (31, 31)] {
+			let period = match classify_critters_glider(top, left) {
+				Some(Classification::Oscillator { period } | Classification::Spaceship { period, .. }) => period,
+				classification => panic!("{:?} at {},{}", classification, top, left)
+			};
+			assert_eq!(period % 2, 0, "odd period at {},{}", top, left);
+		}
+	}
+}
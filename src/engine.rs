@@ -0,0 +1,840 @@
+extern crate rayon;
+
+use rayon::iter::IndexedParallelIterator;
+use rayon::iter::IntoParallelRefIterator;
+use rayon::iter::IntoParallelRefMutIterator;
+use rayon::iter::ParallelIterator;
+use sdl2::sys::_Float32;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::SUB_MATRIX_CHUNK_SIZE;
+use crate::rle::Pattern;
+use crate::rule;
+use crate::rule::Block;
+use crate::rule::Rule;
+use crate::stochastic::Stochastic;
+
+
+// How a sub matrix chunk was handled during an iteration
+#[derive(PartialEq, Clone, Copy)]
+pub enum ChunkEvaluation {
+	Skipped,
+	Border,
+	Full
+}
+
+pub struct IterationStats {
+	pub full_chunks: u32,
+	pub border_chunks: u32,
+	pub births: u64,
+	pub deaths: u64,
+	pub duration: Duration,
+	pub chunk_evaluations: Vec<Vec<ChunkEvaluation>>,
+	chunk_population_deltas: Vec<Vec<i64>>,
+	state_population_deltas: Vec<i64>
+}
+
+// Bounding box of the alive cells, in matrix coordinates (both ends inclusive)
+#[derive(PartialEq, Clone, Copy)]
+pub struct BoundingBox {
+	pub top: u32,
+	pub left: u32,
+	pub bottom: u32,
+	pub right: u32
+}
+
+impl BoundingBox {
+	pub fn width(&self) -> u32 {
+		self.right - self.left + 1
+	}
+
+	pub fn height(&self) -> u32 {
+		self.bottom - self.top + 1
+	}
+
+	// Grows the (possibly empty) box so it contains the given position
+	pub fn include(bounding_box: &mut Option<BoundingBox>, row: u32, col: u32) {
+		*bounding_box = Some(match *bounding_box {
+			Some(current) => BoundingBox {
+				top: current.top.min(row),
+				left: current.left.min(col),
+				bottom: current.bottom.max(row),
+				right: current.right.max(col)
+			},
+			None => BoundingBox { top: row, left: col, bottom: row, right: col }
+		});
+	}
+}
+
+
+// The cell matrix and the rules to iterate it. Keeps track of the whole population, independently of what is being shown
+pub struct Engine {
+	size: u32, // Columns and rows of the matrix
+	sub_size: u32, // Columns and rows of the change matrix, one per chunk of SUB_MATRIX_CHUNK_SIZE x SUB_MATRIX_CHUNK_SIZE cells
+	generation: Vec<Vec<bool>>, // Whether each cell is alive, the only state neighbors count (besides in rule tables)
+	states: Vec<Vec<u8>>, // State of each cell for rules with more than 2 states, empty otherwise
+	state_population: Vec<u64>, // Cells in each state (the dead ones too) for rules with more than 2 states, empty otherwise
+	change_matrix: Vec<Vec<bool>>,
+	chunk_population: Vec<Vec<u32>>,
+	generation_number: i32,
+	population: u64,
+	rule: Rule,
+	range_offsets: Vec<(i32, i32)>, // Cells counted around each cell by Larger than Life rules, empty otherwise
+	rule_map: Vec<Vec<u8>>, // Rule of each chunk in a heterogeneous universe, 0 is the main rule and n the region rule n-1. Empty otherwise
+	region_rules: Vec<Rule>,
+	stochastic: Option<Stochastic>, // Probabilities and noise that make the iterations random, None if they are deterministic
+	last_changes: Vec<Vec<bool>> // Chunks that changed in the previous iteration, for block rules. Empty otherwise
+}
+
+impl Engine {
+	pub fn new() -> Self {
+		Engine::with_size(crate::MATRIX_SIZE)
+	}
+
+	// Smaller matrices are used to run isolated patterns. The size must be a multiple of SUB_MATRIX_CHUNK_SIZE
+	pub fn with_size(size: u32) -> Self {
+		let sub_size = size / SUB_MATRIX_CHUNK_SIZE;
+		Self {
+			size,
+			sub_size,
+			generation: vec![vec![false; size as usize]; size as usize],
+			states: Vec::new(),
+			state_population: Vec::new(),
+			change_matrix: vec![vec![true; sub_size as usize]; sub_size as usize],
+			chunk_population: vec![vec![0; sub_size as usize]; sub_size as usize],
+			generation_number: 0,
+			population: 0,
+			rule: Rule::life(),
+			range_offsets: Vec::new(),
+			rule_map: Vec::new(),
+			region_rules: Vec::new(),
+			stochastic: None,
+			last_changes: Vec::new()
+		}
+	}
+
+	pub fn size(&self) -> u32 {
+		self.size
+	}
+
+	pub fn generation(&self) -> &Vec<Vec<bool>> {
+		&self.generation
+	}
+
+	pub fn change_matrix(&self) -> &Vec<Vec<bool>> {
+		&self.change_matrix
+	}
+
+	pub fn generation_number(&self) -> i32 {
+		self.generation_number
+	}
+
+	pub fn population(&self) -> u64 {
+		self.population
+	}
+
+	// Amount of cells in each state, including the dead ones. Empty for two-state rules, where the population is enough
+	pub fn state_population(&self) -> &Vec<u64> {
+		&self.state_population
+	}
+
+	pub fn rule(&self) -> &Rule {
+		&self.rule
+	}
+
+	// Every chunk is evaluated again on the next generation, cells that were stable may not be anymore
+	pub fn set_rule(&mut self, rule: Rule) {
+		self.range_offsets = rule.range.map_or(Vec::new(), |range| range.offsets());
+		// Dying cells have no meaning in other rules, only the alive ones are kept
+		self.states = match rule.states {
+			2 => Vec::new(),
+			_ => self.generation.iter().map(|row| row.iter().map(|alive| *alive as u8).collect()).collect()
+		};
+		self.rule = rule;
+		self.last_changes.clear();
+		if self.region_rules.iter().any(|region_rule| Engine::mixable(&self.rule, region_rule).is_err()) {
+			self.clear_region_rules();
+		}
+		// Only the noise is kept for rules that can't have probabilities
+		if let Some(stochastic) = self.stochastic.as_mut() {
+			if let Err(error) = stochastic.supports(&self.rule) {
+				println!("[WARNING] Ignoring the probabilities: {}", error);
+				stochastic.birth = [1.0; 9];
+				stochastic.survival = [1.0; 9];
+			}
+		}
+		self.count_states();
+		for row in self.change_matrix.iter_mut() {
+			row.fill(true);
+		}
+	}
+
+	pub fn get(&self, row: usize, col: usize) -> bool {
+		self.generation[row][col]
+	}
+
+	pub fn stochastic(&self) -> Option<&Stochastic> {
+		self.stochastic.as_ref()
+	}
+
+	pub fn set_stochastic(&mut self, stochastic: Option<Stochastic>) -> Result<(), String> {
+		if let Some(stochastic) = stochastic.as_ref() {
+			stochastic.supports(&self.rule)?;
+		}
+		self.stochastic = stochastic;
+		Ok(())
+	}
+
+	// Rules of the regions painted over the main one, see rule_map
+	pub fn region_rules(&self) -> &Vec<Rule> {
+		&self.region_rules
+	}
+
+	// Index of the rule of each chunk, 0 for the main rule and n for the region rule n-1. Empty when there are no regions
+	pub fn rule_map(&self) -> &Vec<Vec<u8>> {
+		&self.rule_map
+	}
+
+	fn rule_at(&self, row: usize, col: usize) -> &Rule {
+		match self.rule_map.get(row / SUB_MATRIX_CHUNK_SIZE as usize).map(|map_row| map_row[col / SUB_MATRIX_CHUNK_SIZE as usize]) {
+			Some(index) if index > 0 => &self.region_rules[index as usize - 1],
+			_ => &self.rule
+		}
+	}
+
+	// Only 2 state rules that look at the immediate neighbors can share the matrix, and square neighborhoods don't mix with
+	// hexagonal ones
+	fn mixable(rule: &Rule, other: &Rule) -> Result<(), String> {
+		for rule in [rule, other] {
+			if rule.states > 2 || rule.range.is_some() || rule.table.is_some() || rule.block.is_some() {
+				return Err(format!("{} can't be mixed with other rules", rule));
+			}
+		}
+		if (rule.neighborhood == rule::Neighborhood::Hexagonal) != (other.neighborhood == rule::Neighborhood::Hexagonal) {
+			return Err(format!("{} and {} have different grids", rule, other));
+		}
+		Ok(())
+	}
+
+	// Applies the rule to every chunk the region touches, so the regions are aligned to SUB_MATRIX_CHUNK_SIZE
+	pub fn set_region_rule(&mut self, region: BoundingBox, rule: Rule) -> Result<(), String> {
+		Engine::mixable(&self.rule, &rule)?;
+		let index = match (rule == self.rule, self.region_rules.iter().position(|region_rule| *region_rule == rule)) {
+			(true, _) => 0,
+			(false, Some(position)) => position + 1,
+			(false, None) if self.region_rules.len() < u8::MAX as usize => {
+				self.region_rules.push(rule);
+				self.region_rules.len()
+			},
+			(false, None) => return Err(format!("there can't be more than {} region rules", u8::MAX))
+		};
+		if self.rule_map.is_empty() {
+			self.rule_map = vec![vec![0; self.sub_size as usize]; self.sub_size as usize];
+		}
+
+		let chunk_size = SUB_MATRIX_CHUNK_SIZE as usize;
+		let (top, left) = (region.top as usize / chunk_size, region.left as usize / chunk_size);
+		let (bottom, right) = (region.bottom as usize / chunk_size, region.right as usize / chunk_size);
+		for sub_row in top..=bottom.min(self.sub_size as usize - 1) {
+			for sub_col in left..=right.min(self.sub_size as usize - 1) {
+				self.rule_map[sub_row][sub_col] = index as u8;
+			}
+		}
+		// The cells of the region and those along its border may behave differently now
+		for sub_row in top.saturating_sub(1)..=(bottom + 1).min(self.sub_size as usize - 1) {
+			for sub_col in left.saturating_sub(1)..=(right + 1).min(self.sub_size as usize - 1) {
+				self.change_matrix[sub_row][sub_col] = true;
+			}
+		}
+		self.forget_unused_region_rules();
+		Ok(())
+	}
+
+	// Region rules no chunk uses anymore are removed, and the map too when it's all the main rule
+	fn forget_unused_region_rules(&mut self) {
+		let mut used = vec![false; self.region_rules.len() + 1];
+		for index in self.rule_map.iter().flatten() {
+			used[*index as usize] = true;
+		}
+		if used.iter().skip(1).all(|used| !used) {
+			self.rule_map.clear();
+			self.region_rules.clear();
+			return;
+		}
+
+		// New index of each rule once the unused ones are gone
+		let mut new_indexes = vec![0; used.len()];
+		let mut next_index = 0;
+		for (index, used) in used.iter().enumerate().skip(1) {
+			if *used {
+				next_index += 1;
+				new_indexes[index] = next_index;
+			}
+		}
+		let mut index = 0;
+		self.region_rules.retain(|_| {
+			index += 1;
+			used[index]
+		});
+		for index in self.rule_map.iter_mut().flatten() {
+			*index = new_indexes[*index as usize];
+		}
+	}
+
+	// Back to a single rule everywhere
+	pub fn clear_region_rules(&mut self) {
+		if self.rule_map.is_empty() {
+			return;
+		}
+		self.rule_map.clear();
+		self.region_rules.clear();
+		for row in self.change_matrix.iter_mut() {
+			row.fill(true);
+		}
+	}
+
+	// 0 is dead, 1 alive and the rest are dying states
+	pub fn get_state(&self, row: usize, col: usize) -> u8 {
+		match self.states.is_empty() {
+			true => self.generation[row][col] as u8,
+			false => self.states[row][col]
+		}
+	}
+
+	pub fn states(&self) -> &Vec<Vec<u8>> {
+		&self.states
+	}
+
+	// States the rule doesn't have are dead
+	pub fn set_state(&mut self, row: usize, col: usize, state: u8) {
+		let state = if state < self.rule.states { state } else { 0 };
+		self.set(row, col, self.rule.alive(state));
+		if !self.states.is_empty() && self.states[row][col] != state {
+			self.state_population[self.states[row][col] as usize] -= 1;
+			self.state_population[state as usize] += 1;
+			self.states[row][col] = state;
+			self.change_matrix[row / SUB_MATRIX_CHUNK_SIZE as usize][col / SUB_MATRIX_CHUNK_SIZE as usize] = true;
+		}
+	}
+
+	pub fn set(&mut self, row: usize, col: usize, alive: bool) {
+		// Clearing a dying cell changes its state but not whether it's alive, the chunk has to be evaluated again all the same
+		if !self.states.is_empty() && self.states[row][col] != alive as u8 {
+			self.state_population[self.states[row][col] as usize] -= 1;
+			self.state_population[alive as usize] += 1;
+			self.states[row][col] = alive as u8;
+			self.change_matrix[row / SUB_MATRIX_CHUNK_SIZE as usize][col / SUB_MATRIX_CHUNK_SIZE as usize] = true;
+		}
+		if self.generation[row][col] == alive {
+			return;
+		}
+
+		self.generation[row][col] = alive;
+		self.change_matrix[row / SUB_MATRIX_CHUNK_SIZE as usize][col / SUB_MATRIX_CHUNK_SIZE as usize] = true; // So the next iteration evaluates it
+		let chunk_population = &mut self.chunk_population[row / SUB_MATRIX_CHUNK_SIZE as usize][col / SUB_MATRIX_CHUNK_SIZE as usize];
+		if alive {
+			*chunk_population += 1;
+			self.population += 1;
+		} else {
+			*chunk_population -= 1;
+			self.population -= 1;
+		}
+	}
+
+	// Replaces the whole matrix (e.g. when restoring a saved state), recounting the population. The states are only needed by
+	// rules with dying states, otherwise they are empty
+	pub fn set_generation(&mut self, generation: Vec<Vec<bool>>, states: Vec<Vec<u8>>) {
+		self.states = match (self.rule.states, states.is_empty()) {
+			(2, _) => Vec::new(),
+			(_, true) => generation.iter().map(|row| row.iter().map(|alive| *alive as u8).collect()).collect(),
+			(_, false) => states
+		};
+		self.generation = generation;
+
+		let generation = &self.generation;
+		self.chunk_population.par_iter_mut().enumerate().for_each(|(sub_row, row_content)| {
+			for (sub_col, elem) in row_content.iter_mut().enumerate() {
+				*elem = generation[sub_row*(SUB_MATRIX_CHUNK_SIZE as usize)..(sub_row+1)*(SUB_MATRIX_CHUNK_SIZE as usize)].iter()
+					.map(|row| row[sub_col*(SUB_MATRIX_CHUNK_SIZE as usize)..(sub_col+1)*(SUB_MATRIX_CHUNK_SIZE as usize)].iter().filter(|alive| **alive).count() as u32)
+					.sum();
+			}
+		});
+		self.population = self.chunk_population.iter().flatten().map(|population| *population as u64).sum();
+		self.count_states();
+	}
+
+	fn count_states(&mut self) {
+		self.state_population = match self.states.is_empty() {
+			true => Vec::new(),
+			false => self.states.par_iter().map(|row| {
+				let mut counts = vec![0; self.rule.states as usize];
+				for state in row {
+					counts[*state as usize] += 1;
+				}
+				counts
+			}).reduce(|| vec![0; self.rule.states as usize], |total, counts| total.iter().zip(counts).map(|(total, count)| total + count).collect())
+		};
+	}
+
+	// Sets the alive cells of the pattern with its top left corner at the given position
+	pub fn load_pattern(&mut self, pattern: &Pattern, top: u32, left: u32) -> Result<(), String> {
+		if pattern.width > self.size || pattern.height > self.size || top > self.size - pattern.height || left > self.size - pattern.width {
+			return Err(format!("the {}x{} pattern does not fit in the {}x{} matrix", pattern.width, pattern.height, self.size, self.size));
+		}
+
+		for (row, col) in pattern.cells.iter() {
+			self.set((top + row) as usize, (left + col) as usize, true);
+		}
+		for (row, col, state) in pattern.states.iter() {
+			self.set_state((top + row) as usize, (left + col) as usize, *state);
+		}
+		Ok(())
+	}
+
+	pub fn clear(&mut self) {
+		self.set_generation(vec![vec![false; self.size as usize]; self.size as usize], Vec::new());
+	}
+
+	// Forgets the simulation progress, so the next iteration evaluates every chunk again
+	pub fn reset(&mut self) {
+		self.change_matrix = vec![vec![true; self.sub_size as usize]; self.sub_size as usize];
+		self.generation_number = 0;
+	}
+
+	// Bounding box of the alive cells inside the given region
+	pub fn bounding_box_in(&self, region: BoundingBox) -> Option<BoundingBox> {
+		let mut bounding_box: Option<BoundingBox> = None;
+		for row in region.top..=region.bottom {
+			for col in region.left..=region.right {
+				if self.generation[row as usize][col as usize] {
+					BoundingBox::include(&mut bounding_box, row, col);
+				}
+			}
+		}
+		bounding_box
+	}
+
+	// Hash of the cells and their states relative to the top left corner of the bounding box of the alive ones, so translated
+	// copies of a pattern share it. The states matter: the states of a rule table or the species of a cell can change while the
+	// same cells stay alive. Only the cells inside the region are hashed, or the whole matrix if there is no region. None if
+	// there are no alive cells. Block rules alternate the grid of blocks (and the table, for those that swap the empty and the
+	// full block) every generation, so the same cells only evolve alike on a generation of the same parity and in the same
+	// position relative to the grid: repetitions take an even amount of generations and cells
+	pub fn normalized_hash(&self, region: Option<BoundingBox>) -> Option<(u64, BoundingBox)> {
+		let bounding_box = match region {
+			Some(region) => self.bounding_box_in(region),
+			None => self.bounding_box()
+		}?;
+
+		let mut hasher = DefaultHasher::new();
+		(bounding_box.width(), bounding_box.height()).hash(&mut hasher);
+		if self.rule.block.is_some() {
+			(self.generation_number.rem_euclid(2), bounding_box.top % 2, bounding_box.left % 2).hash(&mut hasher);
+		}
+		for row in bounding_box.top..=bounding_box.bottom {
+			for col in bounding_box.left..=bounding_box.right {
+				let state = self.get_state(row as usize, col as usize);
+				if state != 0 {
+					(row - bounding_box.top, col - bounding_box.left, state).hash(&mut hasher);
+				}
+			}
+		}
+		Some((hasher.finish(), bounding_box))
+	}
+
+	// Positions (row, col) of the alive cells inside the region, or the whole matrix if there is no region
+	pub fn alive_cells(&self, region: Option<BoundingBox>) -> Vec<(u32, u32)> {
+		let region = match region.or_else(|| self.bounding_box()) {
+			Some(region) => region,
+			None => return Vec::new()
+		};
+
+		let mut cells = Vec::new();
+		for row in region.top..=region.bottom {
+			for col in region.left..=region.right {
+				if self.generation[row as usize][col as usize] {
+					cells.push((row, col));
+				}
+			}
+		}
+		cells
+	}
+
+	// Positions and states (row, col, state) of the cells that aren't dead inside the region. Without a region, dying cells are
+	// looked for around the alive ones, as far as they can be behind them
+	pub fn state_cells(&self, region: Option<BoundingBox>) -> Vec<(u32, u32, u8)> {
+		let region = match (region, self.bounding_box()) {
+			(Some(region), _) => region,
+			(None, Some(bounding_box)) => {
+				let margin = if self.states.is_empty() || self.rule.table.is_some() { 0 } else { self.rule.states as u32 * self.rule.radius() };
+				BoundingBox {
+					top: bounding_box.top.saturating_sub(margin),
+					left: bounding_box.left.saturating_sub(margin),
+					bottom: (bounding_box.bottom + margin).min(self.size - 1),
+					right: (bounding_box.right + margin).min(self.size - 1)
+				}
+			},
+			(None, None) => return Vec::new()
+		};
+
+		let mut cells = Vec::new();
+		for row in region.top..=region.bottom {
+			for col in region.left..=region.right {
+				let state = self.get_state(row as usize, col as usize);
+				if state != 0 {
+					cells.push((row, col, state));
+				}
+			}
+		}
+		cells
+	}
+
+	// The cells of the region (or the whole matrix if there is no region) as a pattern of the current rule, relative to the top
+	// left corner of the region. None without a region if there are no alive cells
+	pub fn to_pattern(&self, region: Option<BoundingBox>) -> Option<Pattern> {
+		let region = region.or_else(|| self.bounding_box())?;
+		let mut cells = Vec::new();
+		let mut states = Vec::new();
+		for row in region.top..=region.bottom {
+			for col in region.left..=region.right {
+				match self.get_state(row as usize, col as usize) {
+					0 => {},
+					1 => cells.push((row - region.top, col - region.left)),
+					state => states.push((row - region.top, col - region.left, state))
+				}
+			}
+		}
+		Some(Pattern {
+			width: region.width(),
+			height: region.height(),
+			cells,
+			states,
+			rule: Some(self.rule.to_string())
+		})
+	}
+
+	// Amount of chunks with at least one alive cell
+	pub fn live_chunks(&self) -> u32 {
+		self.chunk_population.iter().flatten().filter(|population| **population > 0).count() as u32
+	}
+
+	// Only the chunks on the edges of the populated chunk area are scanned cell by cell
+	pub fn bounding_box(&self) -> Option<BoundingBox> {
+		let mut chunk_box: Option<BoundingBox> = None;
+		for (sub_row, row_content) in self.chunk_population.iter().enumerate() {
+			for (sub_col, population) in row_content.iter().enumerate() {
+				if *population > 0 {
+					BoundingBox::include(&mut chunk_box, sub_row as u32, sub_col as u32);
+				}
+			}
+		}
+		let chunk_box = chunk_box?;
+
+		let mut bounding_box: Option<BoundingBox> = None;
+		for sub_row in chunk_box.top..=chunk_box.bottom {
+			for sub_col in chunk_box.left..=chunk_box.right {
+				let on_edge = sub_row == chunk_box.top || sub_row == chunk_box.bottom || sub_col == chunk_box.left || sub_col == chunk_box.right;
+				if !on_edge || self.chunk_population[sub_row as usize][sub_col as usize] == 0 {
+					continue;
+				}
+
+				for row in sub_row*SUB_MATRIX_CHUNK_SIZE..(sub_row+1)*SUB_MATRIX_CHUNK_SIZE {
+					for col in sub_col*SUB_MATRIX_CHUNK_SIZE..(sub_col+1)*SUB_MATRIX_CHUNK_SIZE {
+						if self.generation[row as usize][col as usize] {
+							BoundingBox::include(&mut bounding_box, row, col);
+						}
+					}
+				}
+			}
+		}
+		bounding_box
+	}
+
+	pub fn get_neighbors(&self, target_i : i32, target_j : i32) -> i8 {
+		(self.get_neighborhood(target_i, target_j) & self.rule_at(target_i as usize, target_j as usize).neighborhood.mask()).count_ones() as i8
+	}
+
+	// Which neighbors are alive, as a bitmask in the order of rule::NEIGHBORS. Cells outside of the matrix are dead
+	pub fn get_neighborhood(&self, row: i32, col: i32) -> u8 {
+		let mut neighborhood = 0;
+		for (bit, (row_offset, col_offset)) in rule::NEIGHBORS.iter().enumerate() {
+			let (neighbor_row, neighbor_col) = (row + row_offset, col + col_offset);
+			if neighbor_row >= 0 && neighbor_col >= 0 && neighbor_row < self.size as i32 && neighbor_col < self.size as i32 && self.generation[neighbor_row as usize][neighbor_col as usize] {
+				neighborhood |= 1 << bit;
+			}
+		}
+		neighborhood
+	}
+
+	// Advances one generation and returns the previous one along with the statistics of the iteration
+	pub fn iterate(&mut self) -> (Vec<Vec<bool>>, IterationStats) {
+		if self.stochastic.as_ref().is_some_and(|stochastic| stochastic.needs_every_chunk()) {
+			for (changes, populations) in self.change_matrix.iter_mut().zip(self.chunk_population.iter()) {
+				for (changed, population) in changes.iter_mut().zip(populations.iter()) {
+					*changed |= *population > 0;
+				}
+			}
+		}
+		let (new_generation, new_states, mut new_change_matrix, mut stats) = self.iterate_generation(&self.generation);
+
+		// Blocks shift every generation, so a chunk that stayed the same once may still change in the next one. Only those that
+		// stayed the same twice are stable
+		if self.rule.block.is_some() {
+			let changes = new_change_matrix.clone();
+			for (changed, last_changed) in new_change_matrix.iter_mut().flatten().zip(self.last_changes.iter().flatten()) {
+				*changed |= *last_changed;
+			}
+			self.last_changes = changes;
+		}
+		self.change_matrix = new_change_matrix;
+		for (sub_row, row_content) in stats.chunk_population_deltas.iter().enumerate() {
+			for (sub_col, delta) in row_content.iter().enumerate() {
+				self.chunk_population[sub_row][sub_col] = (self.chunk_population[sub_row][sub_col] as i64 + delta) as u32;
+			}
+		}
+		self.population = self.population + stats.births - stats.deaths;
+		for (population, delta) in self.state_population.iter_mut().zip(stats.state_population_deltas.iter()) {
+			*population = (*population as i64 + delta) as u64;
+		}
+		self.states = new_states;
+		let previous_generation = std::mem::replace(&mut self.generation, new_generation);
+
+		// Noise flips cells after the rule is applied
+		let noise_cells = match (self.stochastic.as_ref(), self.bounding_box()) {
+			(Some(stochastic), Some(bounding_box)) => stochastic.noise_cells(self.generation_number, bounding_box),
+			_ => Vec::new()
+		};
+		for (row, col) in noise_cells {
+			let alive = !self.generation[row][col];
+			self.set(row, col, alive);
+			match alive {
+				true => stats.births += 1,
+				false => stats.deaths += 1
+			}
+		}
+		self.generation_number += 1;
+
+		(previous_generation, stats)
+	}
+
+	// Alive cells in the Larger than Life range of a cell. Cells outside of the matrix are dead
+	fn count_range(&self, row: i32, col: i32) -> u32 {
+		self.range_offsets.iter().filter(|(row_offset, col_offset)| {
+			let (range_row, range_col) = (row + row_offset, col + col_offset);
+			range_row >= 0 && range_col >= 0 && range_row < self.size as i32 && range_col < self.size as i32 && self.generation[range_row as usize][range_col as usize]
+		}).count() as u32
+	}
+
+	// State of a cell once its block is replaced. Cells of the blocks outside of the matrix are dead
+	fn next_in_block(&self, block: &Block, row: usize, col: usize) -> u8 {
+		let (top, left, bit) = Block::locate(self.generation_number, row, col);
+		let mut cells = 0;
+		for (index, (row_offset, col_offset)) in [(0, 0), (0, 1), (1, 0), (1, 1)].iter().enumerate() {
+			let (block_row, block_col) = (top + row_offset, left + col_offset);
+			if block_row >= 0 && block_col >= 0 && block_row < self.size as i64 && block_col < self.size as i64 && self.generation[block_row as usize][block_col as usize] {
+				cells |= 1 << index;
+			}
+		}
+		(block.next(self.generation_number, cells) >> bit) & 1
+	}
+
+	// States of the neighbors of a cell, in the order of rule::NEIGHBORS. Cells outside of the matrix are empty
+	fn neighbor_states(&self, row: i32, col: i32) -> [u8; 8] {
+		let mut states = [0; 8];
+		for (index, (row_offset, col_offset)) in rule::NEIGHBORS.iter().enumerate() {
+			let (neighbor_row, neighbor_col) = (row + row_offset, col + col_offset);
+			if neighbor_row >= 0 && neighbor_col >= 0 && neighbor_row < self.size as i32 && neighbor_col < self.size as i32 {
+				states[index] = self.get_state(neighbor_row as usize, neighbor_col as usize);
+			}
+		}
+		states
+	}
+
+	// Applies the rule to a cell, writing its next state in the new matrices and counting the births and deaths. Returns whether it
+	// changed
+	fn evaluate_cell(&self, row: usize, col: usize, new_generation: &mut [Vec<bool>], new_states: &mut [Vec<u8>], stats: &mut IterationStats) -> bool {
+		let state = self.get_state(row, col);
+		let rule = self.rule_at(row, col);
+		let next_state = match (rule.block.as_ref(), rule.table.as_ref(), rule.range) {
+			(Some(block), _, _) => self.next_in_block(block, row, col),
+			(None, Some(table), _) => table.next(state, &self.neighbor_states(row as i32, col as i32)),
+			(None, None, Some(_)) => rule.next_in_range(state, self.count_range(row as i32, col as i32)),
+			(None, None, None) if rule.species > 1 => rule.next_species(state, &self.neighbor_states(row as i32, col as i32)),
+			(None, None, None) => rule.next(state, self.get_neighborhood(row as i32, col as i32))
+		};
+		// Births and survivals may not happen in stochastic rules, those on neighbor counts
+		let next_state = match self.stochastic.as_ref() {
+			Some(stochastic) if rule.table.is_none() && rule.range.is_none() && rule.block.is_none() => {
+				let neighbors = (self.get_neighborhood(row as i32, col as i32) & rule.neighborhood.mask()).count_ones();
+				stochastic.next(rule, state, next_state, neighbors, stochastic.draw(self.generation_number, row, col))
+			},
+			_ => next_state
+		};
+		if next_state == state {
+			return false;
+		}
+
+		if !new_states.is_empty() {
+			new_states[row][col] = next_state;
+			stats.state_population_deltas[state as usize] -= 1;
+			stats.state_population_deltas[next_state as usize] += 1;
+		}
+		let alive = rule.alive(next_state);
+		if alive == self.generation[row][col] { // From one dying state to the next, or between states of a rule table
+			return true;
+		}
+		new_generation[row][col] = alive;
+		match alive {
+			true => stats.births += 1,
+			false => stats.deaths += 1
+		}
+		stats.chunk_population_deltas[row / SUB_MATRIX_CHUNK_SIZE as usize][col / SUB_MATRIX_CHUNK_SIZE as usize] += if alive { 1 } else { -1 };
+		true
+	}
+
+	fn iterate_generation(&self, generation : &Vec<Vec<bool>>) -> (Vec<Vec<bool>>, Vec<Vec<u8>>, Vec<Vec<bool>>, IterationStats) {
+		let it_start = Instant::now();
+		let mut new_generation : Vec<Vec<bool>> = generation.clone();
+		let mut new_states : Vec<Vec<u8>> = self.states.clone();
+		let mut new_change_matrix : Vec<Vec<bool>> = self.change_matrix.clone();
+		let mut stats = IterationStats {
+			full_chunks: 0,
+			border_chunks: 0,
+			births: 0,
+			deaths: 0,
+			duration: Duration::ZERO,
+			chunk_evaluations: vec![vec![ChunkEvaluation::Skipped; self.sub_size as usize]; self.sub_size as usize],
+			chunk_population_deltas: vec![vec![0; self.sub_size as usize]; self.sub_size as usize],
+			state_population_deltas: vec![0; self.state_population.len()]
+		};
+
+		for sub_row in 0..self.sub_size {
+			for sub_col in 0..self.sub_size {
+
+				let previously_changed = self.change_matrix[sub_row as usize][sub_col as usize];
+				let has_changed_neighbors = self.has_changed_neighbors(sub_row, sub_col);
+				let iterate_sub_matrix = previously_changed || has_changed_neighbors;
+				
+				if iterate_sub_matrix { // Matrix has changed in last iteration
+					let mut has_changed = false;
+
+					if !previously_changed && has_changed_neighbors { // The cell had no changes, but need to check borders in case neighbor cells are moving towards this cell. This reduces iterations on aprox 75%
+						stats.border_chunks += 1;
+						stats.chunk_evaluations[sub_row as usize][sub_col as usize] = ChunkEvaluation::Border;
+						
+						// The border is as wide as the rule's radius, cells further inside can't see the neighbor chunks
+						let width = self.rule.radius();
+						let mut all_elems: Vec<(usize, usize)> = Vec::new();
+						for i in 0..SUB_MATRIX_CHUNK_SIZE {
+							let row_on_border = i < width || i >= SUB_MATRIX_CHUNK_SIZE - width;
+							for j in 0..SUB_MATRIX_CHUNK_SIZE {
+								if row_on_border || j < width || j >= SUB_MATRIX_CHUNK_SIZE - width { // Each cell once, so births and deaths aren't counted twice
+									all_elems.push(((sub_row*SUB_MATRIX_CHUNK_SIZE+i) as usize, (sub_col*SUB_MATRIX_CHUNK_SIZE+j) as usize));
+								}
+							}
+						}
+
+						for elem in all_elems {
+							has_changed |= self.evaluate_cell(elem.0, elem.1, &mut new_generation, &mut new_states, &mut stats);
+						}
+
+
+
+					} else { // The cell had changes
+						stats.full_chunks += 1;
+						stats.chunk_evaluations[sub_row as usize][sub_col as usize] = ChunkEvaluation::Full;
+						for row in sub_row*(SUB_MATRIX_CHUNK_SIZE)..(sub_row+1)*(SUB_MATRIX_CHUNK_SIZE) {
+							for col in sub_col*(SUB_MATRIX_CHUNK_SIZE)..(sub_col+1)*(SUB_MATRIX_CHUNK_SIZE) {
+								has_changed |= self.evaluate_cell(row as usize, col as usize, &mut new_generation, &mut new_states, &mut stats);
+							}
+						}
+					}
+
+
+					new_change_matrix[sub_row as usize][sub_col as usize] = has_changed;
+				}
+			}
+		}
+
+		if it_start.elapsed().as_secs() > 1 {
+			println!("[WARNING] Iteration took {}ms [{}s]", it_start.elapsed().as_millis(), it_start.elapsed().as_millis() as _Float32 / 1000 as _Float32);
+		}
+		stats.duration = it_start.elapsed();
+		return (new_generation, new_states, new_change_matrix, stats);
+	}
+
+	fn has_changed_neighbors(&self, i: u32, j: u32) -> bool {
+		let target_i: i32 = i as i32;
+		let target_j: i32 = j as i32;
+
+
+	
+		let search_i_from = i32::max(0, target_i - 1);
+		let search_i_to = i32::min(self.sub_size as i32, target_i + 2);
+		let search_j_from = i32::max(0, target_j - 1);
+		let search_j_to = i32::min(self.sub_size as i32, target_j + 2);
+	
+		for row in search_i_from..search_i_to {
+			for col in search_j_from..search_j_to {
+				if row != target_i || col != target_j {
+					if self.change_matrix[row as usize][col as usize] == true {
+						return true;
+					}
+				}
+			}
+		}
+		
+		return false;
+	}
+
+	pub fn bruteforce_changes(&mut self) {
+		let it_start = Instant::now();
+
+		let mut new_matrix = vec![vec![true; self.sub_size as usize]; self.sub_size as usize];
+		new_matrix.par_iter_mut().enumerate().for_each(|(sub_row, row_content)| {
+			row_content.par_iter_mut().enumerate().for_each(|(sub_col, elem)| {
+				let mut has_alive_cells = false;
+				for row in sub_row*(SUB_MATRIX_CHUNK_SIZE as usize)..(sub_row+1)*(SUB_MATRIX_CHUNK_SIZE as usize) {
+					for col in sub_col*(SUB_MATRIX_CHUNK_SIZE as usize)..(sub_col+1)*(SUB_MATRIX_CHUNK_SIZE as usize) {
+						if self.get_state(row, col) != 0 { // Alive or dying
+							has_alive_cells = true;
+							break;
+						}
+					}
+					if has_alive_cells {
+						break;
+					}
+				}
+				*elem = has_alive_cells;
+			})
+		});
+
+		self.change_matrix = new_matrix;
+		
+		println!("[INFO] Changes took {}ms [{}s] to load", it_start.elapsed().as_millis(), it_start.elapsed().as_millis() as _Float32 / 1000 as _Float32);
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn swapped_species_hash_differently() {
+		let mut engine = Engine::with_size(SUB_MATRIX_CHUNK_SIZE);
+		engine.set_rule(Rule::parse("Immigration").unwrap());
+		engine.set_state(10, 10, 1);
+		engine.set_state(10, 11, 2);
+		let (hash, _) = engine.normalized_hash(None).unwrap();
+
+		engine.set_state(10, 10, 2);
+		engine.set_state(10, 11, 1);
+		let (swapped_hash, _) = engine.normalized_hash(None).unwrap();
+		assert_ne!(hash, swapped_hash);
+	}
+}
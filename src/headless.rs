@@ -0,0 +1,168 @@
+use crate::census::Census;
+use crate::classification::Classifier;
+use crate::engine::BoundingBox;
+use crate::engine::Engine;
+use crate::image_export;
+use crate::image_export::Scale;
+use crate::rle;
+use crate::rule::Rule;
+use crate::rule_table;
+use crate::stochastic;
+use crate::stochastic::Stochastic;
+use crate::stop_conditions;
+use crate::stop_conditions::StopConditions;
+use crate::svg_export;
+
+
+const DEFAULT_GENERATIONS: i32 = 1000;
+
+const USAGE: &str = "Usage: conway --headless <pattern.rle> [--generations <amount>] [--census <output.csv|output.json>]
+                          [--png <output.png>] [--png-scale <pixels per cell|1/cells per pixel>] [--png-grid]
+                          [--svg <output.svg>] [--svg-grid] [--svg-labels] [--rle <output.rle>]
+                          [--region <rule>@<top>,<left>,<bottom>,<right>]... [randomness] [stop conditions]";
+
+
+struct Options {
+	pattern_path: String,
+	generations: i32,
+	census_path: Option<String>,
+	png_path: Option<String>,
+	png_scale: Option<Scale>, // The biggest that fits if not given
+	png_grid: bool,
+	svg_path: Option<String>,
+	svg_grid: bool,
+	svg_labels: bool,
+	rle_path: Option<String>,
+	regions: Vec<(Rule, i64, i64, i64, i64)> // (rule, top, left, bottom, right) relative to the top left corner of the pattern
+}
+
+// A region rule, like B36/S23@0,-100,50,100
+fn parse_region(value: &str) -> Result<(Rule, i64, i64, i64, i64), String> {
+	let (rule, bounds) = value.rsplit_once('@').ok_or(format!("invalid region \"{}\", expected <rule>@<top>,<left>,<bottom>,<right>", value))?;
+	let bounds = bounds.split(',').map(|bound| bound.trim().parse::<i64>().map_err(|_| format!("invalid region bound \"{}\"", bound))).collect::<Result<Vec<i64>, String>>()?;
+	match bounds[..] {
+		[top, left, bottom, right] if top <= bottom && left <= right => Ok((rule_table::parse_rule(rule)?, top, left, bottom, right)),
+		_ => Err(format!("invalid region bounds \"{}\"", value))
+	}
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+	let mut pattern_path = None;
+	let mut generations = DEFAULT_GENERATIONS;
+	let mut census_path = None;
+	let mut png_path = None;
+	let mut png_scale = None;
+	let mut png_grid = false;
+	let mut svg_path = None;
+	let mut svg_grid = false;
+	let mut svg_labels = false;
+	let mut rle_path = None;
+	let mut regions = Vec::new();
+
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--headless" => {},
+			"--generations" => {
+				let value = args.next().ok_or(format!("missing value for --generations\n{}", USAGE))?;
+				generations = value.parse::<i32>().map_err(|_| format!("invalid amount of generations \"{}\"", value))?;
+			},
+			"--census" => census_path = Some(args.next().ok_or(format!("missing path for --census\n{}", USAGE))?.to_string()),
+			"--png" => png_path = Some(args.next().ok_or(format!("missing path for --png\n{}", USAGE))?.to_string()),
+			"--png-scale" => png_scale = Some(Scale::parse(args.next().ok_or(format!("missing value for --png-scale\n{}", USAGE))?)?),
+			"--png-grid" => png_grid = true,
+			"--svg" => svg_path = Some(args.next().ok_or(format!("missing path for --svg\n{}", USAGE))?.to_string()),
+			"--svg-grid" => svg_grid = true,
+			"--svg-labels" => svg_labels = true,
+			"--rle" => rle_path = Some(args.next().ok_or(format!("missing path for --rle\n{}", USAGE))?.to_string()),
+			"--region" => regions.push(parse_region(args.next().ok_or(format!("missing value for --region\n{}", USAGE))?)?),
+			_ if arg.starts_with("--") => return Err(format!("unknown option \"{}\"\n{}\n{}\n{}", arg, USAGE, stochastic::USAGE, stop_conditions::USAGE)),
+			_ => pattern_path = Some(arg.to_string())
+		}
+	}
+
+	Ok(Options {
+		pattern_path: pattern_path.ok_or(format!("missing pattern file\n{}", USAGE))?,
+		generations,
+		census_path,
+		png_path,
+		png_scale,
+		png_grid,
+		svg_path,
+		svg_grid,
+		svg_labels,
+		rle_path,
+		regions
+	})
+}
+
+// Runs a pattern without a window until it stabilises or the generation limit is reached, and reports the result
+pub fn run(args: &[String]) -> Result<(), String> {
+	let (stop_conditions, args) = StopConditions::parse_args(args)?;
+	let (stochastic, args) = Stochastic::parse_args(&args)?;
+	let options = parse_options(&args)?;
+	let pattern = rle::load(options.pattern_path.as_str())?;
+	let mut engine = Engine::new();
+	engine.set_rule(pattern.parsed_rule()?);
+	let (top, left) = (crate::MATRIX_SIZE.saturating_sub(pattern.height) / 2, crate::MATRIX_SIZE.saturating_sub(pattern.width) / 2);
+	engine.load_pattern(&pattern, top, left)?;
+	for (rule, region_top, region_left, region_bottom, region_right) in options.regions {
+		let to_matrix = |offset: i64, start: u32| (start as i64 + offset).clamp(0, crate::MATRIX_SIZE as i64 - 1) as u32;
+		let region = BoundingBox {
+			top: to_matrix(region_top, top),
+			left: to_matrix(region_left, left),
+			bottom: to_matrix(region_bottom, top),
+			right: to_matrix(region_right, left)
+		};
+		println!("Region: {} on rows {}..{} and cols {}..{} (whole chunks of {} cells)", rule, region_top, region_bottom, region_left, region_right, crate::SUB_MATRIX_CHUNK_SIZE);
+		engine.set_region_rule(region, rule)?;
+	}
+	if let Some(stochastic) = stochastic {
+		engine.set_stochastic(Some(stochastic.clone()))?;
+		println!("Random iterations: {}", stochastic);
+	}
+
+	let mut classifier = Classifier::new(None);
+	let mut classification = classifier.observe(&engine);
+	let mut stop_reason = stop_conditions.check(&engine, classification, engine.population());
+	while stop_reason.is_none() && engine.generation_number() < options.generations {
+		let previous_population = engine.population();
+		engine.iterate();
+		classification = classifier.observe(&engine);
+		stop_reason = stop_conditions.check(&engine, classification, previous_population);
+	}
+
+	println!("Generations: {}", engine.generation_number());
+	println!("Population: {}", engine.population());
+	match classification {
+		Some((classification, since)) => println!("Classification: {} since generation {}", classification, since),
+		None => println!("Classification: unknown, no repetition found in {} generations", engine.generation_number())
+	}
+	match stop_reason {
+		Some(stop_reason) => println!("Stopped: {}", stop_reason),
+		None => println!("Stopped: generation limit of {} reached", options.generations)
+	}
+
+	// Tally of the objects the final generation is made of
+	if let Some(census_path) = options.census_path {
+		let census = Census::take(&engine, None);
+		census.print();
+		census.save(census_path.as_str())?;
+		println!("Census saved to {}", census_path);
+	}
+
+	if let Some(png_path) = options.png_path {
+		image_export::save_pattern(&engine, png_path.as_str(), options.png_scale, options.png_grid)?;
+		println!("Image saved to {}", png_path);
+	}
+	if let Some(svg_path) = options.svg_path {
+		svg_export::save(&engine, None, svg_path.as_str(), options.svg_grid, options.svg_labels)?;
+		println!("Vector image saved to {}", svg_path);
+	}
+	if let Some(rle_path) = options.rle_path {
+		let pattern = engine.to_pattern(None).ok_or("there are no alive cells to save")?;
+		rle::save(rle_path.as_str(), &pattern, &[format!("Generation {}", engine.generation_number())])?;
+		println!("Pattern saved to {}", rle_path);
+	}
+	Ok(())
+}
@@ -0,0 +1,126 @@
+extern crate sdl2;
+
+
+use sdl2::pixels::Color;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::surface::Surface;
+
+use crate::SUB_MATRIX_CHUNK_SIZE;
+use crate::SUB_MATRIX_SIZE;
+
+
+const CHUNK_CELLS: usize = (SUB_MATRIX_CHUNK_SIZE * SUB_MATRIX_CHUNK_SIZE) as usize;
+
+// Accumulated amount of state changes of every cell during a run.
+// Chunks are only allocated once any of their cells changes, so quiet areas of the matrix cost nothing.
+pub struct Heatmap {
+	chunks: Vec<Option<Vec<u32>>>,
+	max: u32
+}
+
+impl Heatmap {
+	pub fn new() -> Self {
+		Self {
+			chunks: vec![None; (SUB_MATRIX_SIZE * SUB_MATRIX_SIZE) as usize],
+			max: 0
+		}
+	}
+
+	pub fn reset(&mut self) {
+		self.chunks.iter_mut().for_each(|chunk| *chunk = None);
+		self.max = 0;
+	}
+
+	// Adds one to every cell that differs between both generations. Only chunks flagged in the change matrix are compared
+	pub fn record(&mut self, old_generation: &[Vec<bool>], new_generation: &[Vec<bool>], change_matrix: &[Vec<bool>]) {
+		for (sub_row, changed_row) in change_matrix.iter().enumerate() {
+			for (sub_col, &changed) in changed_row.iter().enumerate() {
+				if !changed {
+					continue;
+				}
+
+				let chunk = self.chunks[sub_row * SUB_MATRIX_SIZE as usize + sub_col].get_or_insert_with(|| vec![0; CHUNK_CELLS]);
+				for i in 0..SUB_MATRIX_CHUNK_SIZE as usize {
+					for j in 0..SUB_MATRIX_CHUNK_SIZE as usize {
+						let row = sub_row * SUB_MATRIX_CHUNK_SIZE as usize + i;
+						let col = sub_col * SUB_MATRIX_CHUNK_SIZE as usize + j;
+						if old_generation[row][col] != new_generation[row][col] {
+							let heat = &mut chunk[i * SUB_MATRIX_CHUNK_SIZE as usize + j];
+							*heat += 1;
+							self.max = u32::max(self.max, *heat);
+						}
+					}
+				}
+			}
+		}
+	}
+
+	pub fn get(&self, row: u32, col: u32) -> u32 {
+		let chunk_index = (row / SUB_MATRIX_CHUNK_SIZE) * SUB_MATRIX_SIZE + col / SUB_MATRIX_CHUNK_SIZE;
+		match &self.chunks[chunk_index as usize] {
+			Some(chunk) => chunk[((row % SUB_MATRIX_CHUNK_SIZE) * SUB_MATRIX_CHUNK_SIZE + col % SUB_MATRIX_CHUNK_SIZE) as usize],
+			None => 0
+		}
+	}
+
+	// Maps a heat value to a blue -> yellow -> red gradient. A logarithmic scale keeps rarely changing cells visible next to oscillators
+	pub fn color(&self, heat: u32) -> Color {
+		if heat == 0 || self.max == 0 {
+			return Color::RGB(0, 0, 0);
+		}
+
+		let t = ((1 + heat) as f32).ln() / ((1 + self.max) as f32).ln();
+		let (from, to, t) = if t < 0.5 {
+			(crate::COLOR_BLUE, crate::COLOR_YELLOW, t * 2.0)
+		} else {
+			(crate::COLOR_YELLOW, crate::COLOR_RED, (t - 0.5) * 2.0)
+		};
+		let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) as u8;
+
+		Color::RGB(lerp(from.r, to.r), lerp(from.g, to.g), lerp(from.b, to.b))
+	}
+
+	// Bounding box (top row, left col, rows, cols) of the allocated chunks, or None if nothing changed yet
+	fn active_bounds(&self) -> Option<(u32, u32, u32, u32)> {
+		let mut bounds: Option<(u32, u32, u32, u32)> = None;
+		for (index, chunk) in self.chunks.iter().enumerate() {
+			if chunk.is_some() {
+				let sub_row = index as u32 / SUB_MATRIX_SIZE;
+				let sub_col = index as u32 % SUB_MATRIX_SIZE;
+				bounds = Some(match bounds {
+					Some((min_row, min_col, max_row, max_col)) => (min_row.min(sub_row), min_col.min(sub_col), max_row.max(sub_row), max_col.max(sub_col)),
+					None => (sub_row, sub_col, sub_row, sub_col)
+				});
+			}
+		}
+
+		bounds.map(|(min_row, min_col, max_row, max_col)| (
+			min_row * SUB_MATRIX_CHUNK_SIZE,
+			min_col * SUB_MATRIX_CHUNK_SIZE,
+			(max_row - min_row + 1) * SUB_MATRIX_CHUNK_SIZE,
+			(max_col - min_col + 1) * SUB_MATRIX_CHUNK_SIZE
+		))
+	}
+
+	// Saves the active area of the heatmap as a BMP image, one pixel per cell
+	pub fn export_bmp(&self, path: &str) -> Result<(), String> {
+		let (top_row, left_col, rows, cols) = match self.active_bounds() {
+			Some(bounds) => bounds,
+			None => return Err("the heatmap is empty".to_string())
+		};
+
+		let mut surface = Surface::new(cols, rows, PixelFormatEnum::RGB24)?;
+		let pitch = surface.pitch() as usize;
+		surface.with_lock_mut(|pixels| {
+			for row in 0..rows {
+				for col in 0..cols {
+					let color = self.color(self.get(top_row + row, left_col + col));
+					let offset = row as usize * pitch + col as usize * 3;
+					pixels[offset..offset + 3].copy_from_slice(&[color.r, color.g, color.b]);
+				}
+			}
+		});
+
+		surface.save_bmp(path)
+	}
+}
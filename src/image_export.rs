@@ -0,0 +1,162 @@
+use std::fs::File;
+use std::io::BufWriter;
+
+use sdl2::pixels::Color;
+
+use crate::engine::BoundingBox;
+use crate::engine::Engine;
+use crate::rule::Rule;
+
+
+const MAX_IMAGE_SIDE: u32 = 16384; // Widest or tallest image that can be exported, in pixels
+const FIT_IMAGE_SIDE: u32 = 4096; // Widest or tallest image when the scale is chosen automatically
+const MIN_GRID_SCALE: u32 = 4; // Pixels per cell needed to fit the grid lines, with smaller cells they would hide the pattern
+
+
+// Zoom of the exported image
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Scale {
+	PixelsPerCell(u32), // Each cell is a square of pixels
+	CellsPerPixel(u32) // Each pixel is a square of cells, alive if any of them is (or else in the lowest dying state among them)
+}
+
+impl Scale {
+	// "4" is 4 pixels per cell, "1/4" is one pixel for each 4x4 cells
+	pub fn parse(text: &str) -> Result<Self, String> {
+		let (amount, scale): (&str, fn(u32) -> Scale) = match text.strip_prefix("1/") {
+			Some(cells) => (cells, Scale::CellsPerPixel),
+			None => (text, Scale::PixelsPerCell)
+		};
+		match amount.parse::<u32>() {
+			Ok(amount) if amount > 0 => Ok(scale(amount)),
+			_ => Err(format!("invalid scale \"{}\", expected pixels per cell (e.g. 4) or cells per pixel (e.g. 1/4)", text))
+		}
+	}
+
+	// Biggest zoom (up to the size of the cells on screen) at which the region fits in the image size limit
+	pub fn fit(region: BoundingBox, max_side: u32) -> Self {
+		let side = u32::max(region.width(), region.height());
+		match max_side / side {
+			0 => Scale::CellsPerPixel(side.div_ceil(max_side)),
+			pixels => Scale::PixelsPerCell(u32::min(pixels, crate::SIZE))
+		}
+	}
+
+	// Side in pixels of a side of cells
+	pub fn pixels(&self, cells: u32) -> u32 {
+		match self {
+			Scale::PixelsPerCell(pixels) => cells * pixels,
+			Scale::CellsPerPixel(cells_per_pixel) => cells.div_ceil(*cells_per_pixel)
+		}
+	}
+}
+
+// RGB pixels, row by row
+pub struct Image {
+	pub width: u32,
+	pub height: u32,
+	pub pixels: Vec<u8>
+}
+
+impl Image {
+	fn set(&mut self, x: u32, y: u32, color: Color) {
+		let offset = ((y * self.width + x) * 3) as usize;
+		self.pixels[offset..offset + 3].copy_from_slice(&[color.r, color.g, color.b]);
+	}
+
+	pub fn save_png(&self, path: &str) -> Result<(), String> {
+		let file = File::create(path).map_err(|error| format!("could not create {}: {}", path, error))?;
+		let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+		encoder.set_color(png::ColorType::Rgb);
+		encoder.set_depth(png::BitDepth::Eight);
+		let mut writer = encoder.write_header().map_err(|error| format!("could not write {}: {}", path, error))?;
+		writer.write_image_data(&self.pixels).map_err(|error| format!("could not write {}: {}", path, error))
+	}
+}
+
+// Colors of the grid on screen: alive and dying cells, and the dead ones on a chessboard of big squares
+fn cell_color(state: u8, rule: &Rule, row: u32, col: u32) -> Color {
+	if state != 0 {
+		crate::state_color(state, rule)
+	} else if (row / crate::GRID_BIG_CELL_SIZE) % 2 == (col / crate::GRID_BIG_CELL_SIZE) % 2 {
+		crate::COLOR_BLACK_1
+	} else {
+		Color::BLACK
+	}
+}
+
+// Draws the cells of the region like the grid on screen. Grid lines are only drawn when the cells are big enough
+pub fn render(engine: &Engine, region: BoundingBox, scale: Scale, grid: bool) -> Result<Image, String> {
+	let (width, height) = (scale.pixels(region.width()), scale.pixels(region.height()));
+	if width > MAX_IMAGE_SIDE || height > MAX_IMAGE_SIDE {
+		return Err(format!("the {}x{} image is too big, the limit is {}x{} pixels", width, height, MAX_IMAGE_SIDE, MAX_IMAGE_SIDE));
+	}
+
+	let rule = engine.rule();
+	let mut image = Image {
+		width,
+		height,
+		pixels: vec![0; (width * height * 3) as usize]
+	};
+	match scale {
+		Scale::PixelsPerCell(pixels) => {
+			for row in 0..region.height() {
+				for col in 0..region.width() {
+					let color = cell_color(engine.get_state((region.top + row) as usize, (region.left + col) as usize), rule, region.top + row, region.left + col);
+					for y in row * pixels..(row + 1) * pixels {
+						for x in col * pixels..(col + 1) * pixels {
+							image.set(x, y, color);
+						}
+					}
+				}
+			}
+
+			if grid && pixels >= MIN_GRID_SCALE {
+				for y in 0..height {
+					for x in 0..width {
+						if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+							image.set(x, y, crate::COLOR_BLACK_3);
+						} else if x % pixels == 0 || y % pixels == 0 {
+							image.set(x, y, crate::COLOR_BLACK_2);
+						}
+					}
+				}
+			}
+		},
+		Scale::CellsPerPixel(cells) => {
+			for y in 0..height {
+				for x in 0..width {
+					let (top, left) = (region.top + y * cells, region.left + x * cells);
+					let state = (top..u32::min(top + cells, region.bottom + 1))
+						.flat_map(|row| (left..u32::min(left + cells, region.right + 1)).map(move |col| engine.get_state(row as usize, col as usize)))
+						.filter(|state| *state != 0)
+						.min()
+						.unwrap_or(0);
+					image.set(x, y, cell_color(state, rule, top, left));
+				}
+			}
+		}
+	}
+	Ok(image)
+}
+
+// Saves the whole pattern, at the given scale or the biggest one that fits
+pub fn save_pattern(engine: &Engine, path: &str, scale: Option<Scale>, grid: bool) -> Result<(), String> {
+	let bounding_box = engine.bounding_box().ok_or("the universe is empty".to_string())?;
+	render(engine, bounding_box, scale.unwrap_or(Scale::fit(bounding_box, FIT_IMAGE_SIDE)), grid)?.save_png(path)
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn scale_parse() {
+		assert_eq!(Scale::parse("4"), Ok(Scale::PixelsPerCell(4)));
+		assert_eq!(Scale::parse("1/4"), Ok(Scale::CellsPerPixel(4)));
+		for text in ["0", "1/0", "-2", "1/", "x"] {
+			assert!(Scale::parse(text).is_err(), "{} should be rejected", text);
+		}
+	}
+}
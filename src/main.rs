@@ -2,12 +2,36 @@ extern crate sdl2;
 
 mod button;
 mod button_icon;
+mod census;
+mod classification;
+mod engine;
+mod headless;
+mod heatmap;
+mod image_export;
+mod methuselah_search;
+mod minimap;
+mod predecessor;
+mod recording;
+mod rle;
+mod rule;
+mod rule_editor;
+mod rule_table;
 mod scene;
+mod ship_search;
+mod soup_search;
+mod stabilisation;
+mod statistics;
+mod stochastic;
+mod stop_conditions;
+mod svg_export;
 
 use std::time::Duration;
 
+use rule::Rule;
 use scene::Scene;
 use sdl2::{pixels::Color, ttf::FontStyle};
+use stochastic::Stochastic;
+use stop_conditions::StopConditions;
 
 
 // Generation vector size
@@ -31,6 +55,7 @@ const GRID_HEIGHT: u32 = SIZE * VIEW_ROWS;
 const H_MARGIN : u32 = 20;
 const V_MARGIN : u32 = 40;
 const TOOLBAR_HEIGHT : u32 = 30;
+const SIDE_PANEL_WIDTH : u32 = 256; // Width of the right panel holding the minimap and the statistics
 
 // Cooldown to wait between each generation iteration
 const ITERATION_COOLDOWN : Duration = std::time::Duration::from_millis(200);
@@ -44,6 +69,27 @@ const COLOR_WHITE: Color =	 Color::RGB(205, 217, 229);
 const COLOR_BLACK_1: Color = Color::RGB(20, 20, 20);
 const COLOR_BLACK_2: Color = Color::RGB(40, 40, 40);
 const COLOR_BLACK_3: Color = Color::RGB(80, 80, 80);
+const SPECIES_COLORS: [Color; 4] = [COLOR_WHITE, COLOR_RED, COLOR_BLUE, COLOR_YELLOW]; // Of Immigration and QuadLife
+
+// Color of a cell in a rule with dying states: alive cells are white, and dying ones fade from blue to the background as they
+// get closer to dead. Each species of multi-species rules has its own color, and rule tables may have theirs
+fn state_color(state: u8, rule: &Rule) -> Color {
+	if let Some((r, g, b)) = rule.table.as_ref().and_then(|table| table.colors.get(state as usize).copied().flatten()) {
+		return Color::RGB(r, g, b);
+	}
+	if rule.species > 1 && state <= rule.species {
+		return SPECIES_COLORS[(state as usize - 1) % SPECIES_COLORS.len()];
+	}
+	if state <= 1 {
+		return COLOR_WHITE;
+	}
+	let fade = 0.75 * (state - 2) as f32 / u8::max(rule.states.saturating_sub(3), 1) as f32;
+	let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * fade).round() as u8;
+	Color::RGB(mix(COLOR_BLUE.r, COLOR_BLACK_2.r), mix(COLOR_BLUE.g, COLOR_BLACK_2.g), mix(COLOR_BLUE.b, COLOR_BLACK_2.b))
+}
+
+// Entry point of a mode that runs without a window, given every argument
+type HeadlessMode = fn(&[String]) -> Result<(), String>;
 
 
 pub fn main() {
@@ -51,25 +97,63 @@ pub fn main() {
 		panic!("[ERROR] Total population rows x cols should be greater than shown rows x cols");
 	}
 
+	let args: Vec<String> = std::env::args().skip(1).collect();
+
+	// Modes that run without a window
+	let headless_modes: [(&str, HeadlessMode); 6] = [
+		("--headless", headless::run),
+		("--soup-search", soup_search::run),
+		("--methuselah-search", methuselah_search::run),
+		("--predecessor", predecessor::run),
+		("--ship-search", ship_search::run),
+		("--record", recording::run)
+	];
+	if let Some((_, run)) = headless_modes.iter().find(|(flag, _)| args.iter().any(|arg| arg == flag)) {
+		if let Err(error) = run(&args) {
+			eprintln!("[ERROR] {}", error);
+			std::process::exit(1);
+		}
+		return;
+	}
+
+	// The only arguments besides the stop conditions and the randomness are an optional pattern to open
+	let (stop_conditions, stochastic, pattern_path) = match StopConditions::parse_args(&args).and_then(|(stop_conditions, remaining)| Ok((stop_conditions, Stochastic::parse_args(&remaining)?))) {
+		Ok((_, (_, remaining))) if remaining.len() > 1 || remaining.iter().any(|arg| arg.starts_with("--")) => {
+			eprintln!("[ERROR] unknown option \"{}\"\n{}\n{}", remaining.iter().find(|arg| arg.starts_with("--")).unwrap_or(&remaining[1]), stop_conditions::USAGE, stochastic::USAGE);
+			std::process::exit(1);
+		},
+		Ok((stop_conditions, (stochastic, remaining))) => (stop_conditions, stochastic, remaining.first().cloned()),
+		Err(error) => {
+			eprintln!("[ERROR] {}", error);
+			std::process::exit(1);
+		}
+	};
+
 
 	let sdl_context = sdl2::init().unwrap();
 	let ttf_context = sdl2::ttf::init().unwrap();
 	let video_subsystem = sdl_context.video().unwrap();
 
 	let window = video_subsystem
-		.window("Conway's Game Of Life :: by keelus", VIEW_COLS * SIZE + H_MARGIN * 2, VIEW_ROWS * SIZE + V_MARGIN + 20 + TOOLBAR_HEIGHT)
+		.window("Conway's Game Of Life :: by keelus", VIEW_COLS * SIZE + H_MARGIN * 3 + SIDE_PANEL_WIDTH, VIEW_ROWS * SIZE + V_MARGIN + 20 + TOOLBAR_HEIGHT)
 		.position_centered()
 		.build()
 		.unwrap();
 
 	let canvas: sdl2::render::Canvas<sdl2::video::Window> = window.into_canvas().build().unwrap();
 
-	let mut scene = Scene::new(canvas);
+	if let Some(stochastic) = stochastic.as_ref() {
+		println!("[INFO] Random iterations: {}", stochastic);
+	}
+	let mut scene = Scene::new(canvas, stop_conditions, stochastic);
 	scene.initialize({
 		let mut main_font = ttf_context.load_font("./fonts/EnvyCodeR_bold.ttf", 15).unwrap();
 		main_font.set_style(FontStyle::BOLD);
 		main_font
-	});
+	}, ttf_context.load_font("./fonts/EnvyCodeR_regular.ttf", 9).unwrap());
+	if let Some(pattern_path) = pattern_path {
+		scene.load_pattern_file(pattern_path.as_str());
+	}
 	
 	let mut event_pump: sdl2::EventPump = sdl_context.event_pump().unwrap();
 
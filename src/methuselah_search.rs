@@ -0,0 +1,244 @@
+use std::collections::HashSet;
+use std::fs;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use rand::Rng;
+use rand::SeedableRng;
+use rand::seq::index;
+use rand_chacha::ChaCha8Rng;
+use rayon::iter::IntoParallelRefIterator;
+use rayon::iter::ParallelIterator;
+
+use crate::rle;
+use crate::rle::Pattern;
+use crate::stabilisation;
+use crate::stochastic::hash_name;
+
+
+const MAX_ENUMERATED_CELLS: u32 = 24; // Bigger boxes have too many patterns to try them all, they have to be sampled
+const DEFAULT_SIZE: u32 = 4;
+const DEFAULT_TOP: usize = 10;
+const DEFAULT_MAX_GENERATIONS: i32 = 50000;
+const DEFAULT_OUTPUT: &str = "methuselahs";
+
+const USAGE: &str = "Usage: conway --methuselah-search [--width <cells>] [--height <cells>] [--cells <amount>] [--samples <amount>] [--seed <seed>]
+                                [--rank lifespan|population|bounding-box] [--top <amount>] [--max-generations <amount>] [--output <directory>]
+Every pattern fitting in the box is tried, unless --samples is given to try random ones instead";
+
+
+#[derive(PartialEq, Clone, Copy)]
+enum Rank {
+	Lifespan,
+	Population,
+	BoundingBox
+}
+
+struct Options {
+	width: u32,
+	height: u32,
+	cells: Option<u32>,
+	samples: Option<u32>,
+	seed: String,
+	rank: Rank,
+	top: usize,
+	max_generations: i32,
+	output: String
+}
+
+fn parse_value<T: std::str::FromStr>(args: &mut std::slice::Iter<String>, option: &str) -> Result<T, String> {
+	let value = args.next().ok_or(format!("missing value for {}\n{}", option, USAGE))?;
+	value.parse::<T>().map_err(|_| format!("invalid value \"{}\" for {}", value, option))
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+	let mut options = Options {
+		width: DEFAULT_SIZE,
+		height: DEFAULT_SIZE,
+		cells: None,
+		samples: None,
+		seed: SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs()).to_string(),
+		rank: Rank::Lifespan,
+		top: DEFAULT_TOP,
+		max_generations: DEFAULT_MAX_GENERATIONS,
+		output: DEFAULT_OUTPUT.to_string()
+	};
+
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--methuselah-search" => {},
+			"--width" => options.width = parse_value(&mut args, arg)?,
+			"--height" => options.height = parse_value(&mut args, arg)?,
+			"--cells" => options.cells = Some(parse_value(&mut args, arg)?),
+			"--samples" => options.samples = Some(parse_value(&mut args, arg)?),
+			"--seed" => options.seed = parse_value(&mut args, arg)?,
+			"--rank" => options.rank = match args.next().map(|value| value.as_str()) {
+				Some("lifespan") => Rank::Lifespan,
+				Some("population") => Rank::Population,
+				Some("bounding-box") => Rank::BoundingBox,
+				_ => return Err(format!("--rank must be lifespan, population or bounding-box\n{}", USAGE))
+			},
+			"--top" => options.top = parse_value(&mut args, arg)?,
+			"--max-generations" => options.max_generations = parse_value(&mut args, arg)?,
+			"--output" => options.output = parse_value(&mut args, arg)?,
+			_ => return Err(format!("unknown option \"{}\"\n{}", arg, USAGE))
+		}
+	}
+
+	if options.width == 0 || options.height == 0 {
+		return Err("the box must be at least 1x1".to_string());
+	}
+	if options.width > stabilisation::UNIVERSE_SIZE || options.height > stabilisation::UNIVERSE_SIZE {
+		return Err(format!("the box can't be bigger than the {}x{} universe the patterns run in", stabilisation::UNIVERSE_SIZE, stabilisation::UNIVERSE_SIZE));
+	}
+	let area = options.width as u64 * options.height as u64;
+	if options.cells.is_some_and(|cells| cells as u64 > area) {
+		return Err(format!("{} cells don't fit in a {}x{} box", options.cells.unwrap_or(0), options.width, options.height));
+	}
+	if options.samples.is_none() && area > MAX_ENUMERATED_CELLS as u64 {
+		return Err(format!("boxes bigger than {} cells can't be enumerated, use --samples", MAX_ENUMERATED_CELLS));
+	}
+	Ok(options)
+}
+
+// Rotations and reflections that keep the pattern inside the box, only the first 4 if it isn't square
+fn transform(transformation: usize, row: u32, col: u32, width: u32, height: u32) -> (u32, u32) {
+	match transformation {
+		0 => (row, col),
+		1 => (row, width - 1 - col),
+		2 => (height - 1 - row, col),
+		3 => (height - 1 - row, width - 1 - col),
+		4 => (col, row),
+		5 => (col, width - 1 - row),
+		6 => (height - 1 - col, row),
+		_ => (height - 1 - col, width - 1 - row)
+	}
+}
+
+// The first of the pattern rotated and reflected in every way
+fn canonical(cells: &[(u32, u32)], width: u32, height: u32) -> Vec<(u32, u32)> {
+	let transformations = if width == height { 8 } else { 4 };
+	(0..transformations).map(|transformation| {
+		let mut transformed: Vec<(u32, u32)> = cells.iter().map(|(row, col)| transform(transformation, *row, *col, width, height)).collect();
+		transformed.sort();
+		transformed
+	}).min().unwrap_or_default()
+}
+
+// Patterns touching every side of the box, so smaller boxes aren't tried again, only one of each rotation or reflection
+fn enumerate(options: &Options) -> Vec<Vec<(u32, u32)>> {
+	let area = options.width * options.height;
+	let mut patterns = Vec::new();
+	for mask in 1u32..(1 << area) {
+		if options.cells.is_some_and(|cells| mask.count_ones() != cells) {
+			continue;
+		}
+
+		let cells: Vec<(u32, u32)> = (0..area).filter(|bit| mask & (1 << bit) != 0).map(|bit| (bit / options.width, bit % options.width)).collect();
+		let fills_box = cells.iter().any(|cell| cell.0 == 0) && cells.iter().any(|cell| cell.0 == options.height - 1)
+			&& cells.iter().any(|cell| cell.1 == 0) && cells.iter().any(|cell| cell.1 == options.width - 1);
+		if fills_box && canonical(&cells, options.width, options.height) == cells {
+			patterns.push(cells);
+		}
+	}
+	patterns
+}
+
+// Random patterns, with the amount of cells if given or half of the box alive on average otherwise. Repeated ones are dropped
+fn sample(options: &Options, samples: u32) -> Vec<Vec<(u32, u32)>> {
+	let mut rng = ChaCha8Rng::seed_from_u64(hash_name(options.seed.as_str()));
+	let area = options.width * options.height;
+	let mut seen = HashSet::new();
+	let mut patterns = Vec::new();
+	for _ in 0..samples {
+		let mut cells: Vec<(u32, u32)> = match options.cells {
+			Some(cells) => index::sample(&mut rng, area as usize, cells as usize).iter().map(|bit| (bit as u32 / options.width, bit as u32 % options.width)).collect(),
+			None => (0..area).filter(|_| rng.gen_bool(0.5)).map(|bit| (bit / options.width, bit % options.width)).collect()
+		};
+		cells.sort();
+		if !cells.is_empty() && seen.insert(canonical(&cells, options.width, options.height)) {
+			patterns.push(cells);
+		}
+	}
+	patterns
+}
+
+struct Candidate {
+	cells: Vec<(u32, u32)>,
+	lifespan: i32,
+	stabilised: bool,
+	final_population: u64,
+	max_bounding_box: (u32, u32)
+}
+
+fn run_candidate(cells: &[(u32, u32)], max_generations: i32) -> Candidate {
+	let pattern = Pattern {
+		width: cells.iter().map(|cell| cell.1 + 1).max().unwrap_or(0),
+		height: cells.iter().map(|cell| cell.0 + 1).max().unwrap_or(0),
+		cells: cells.to_vec(),
+		states: Vec::new(),
+		rule: None
+	};
+	let mut engine = stabilisation::load_centered(&pattern).expect("the pattern fits in the universe");
+	let outcome = stabilisation::run(&mut engine, max_generations);
+
+	Candidate {
+		cells: cells.to_vec(),
+		lifespan: outcome.lifespan,
+		stabilised: outcome.period.is_some(),
+		final_population: engine.population() + outcome.escaped.entries.iter().map(|entry| entry.count as u64 * entry.sample.len() as u64).sum::<u64>(),
+		max_bounding_box: outcome.max_bounding_box
+	}
+}
+
+// Runs small starting patterns until they stabilise, ranks them and saves the best ones as RLE
+pub fn run(args: &[String]) -> Result<(), String> {
+	let options = parse_options(args)?;
+	fs::create_dir_all(options.output.as_str()).map_err(|error| format!("could not create {}: {}", options.output, error))?;
+
+	let patterns = match options.samples {
+		Some(samples) => {
+			println!("Sampling {} random {}x{} patterns with the seed \"{}\"", samples, options.width, options.height, options.seed);
+			sample(&options, samples)
+		},
+		None => enumerate(&options)
+	};
+	println!("Running {} distinct {}x{} patterns", patterns.len(), options.width, options.height);
+
+	let start = Instant::now();
+	let mut candidates: Vec<Candidate> = patterns.par_iter().map(|cells| run_candidate(cells, options.max_generations)).collect();
+	candidates.sort_by(|a, b| {
+		let lifespan = b.lifespan.cmp(&a.lifespan);
+		match options.rank {
+			Rank::Lifespan => lifespan,
+			Rank::Population => b.final_population.cmp(&a.final_population).then(lifespan),
+			Rank::BoundingBox => (b.max_bounding_box.0 * b.max_bounding_box.1).cmp(&(a.max_bounding_box.0 * a.max_bounding_box.1)).then(lifespan)
+		}
+	});
+	println!("Ran {} patterns in {:.2}s", candidates.len(), start.elapsed().as_secs_f32());
+
+	println!("{:>4} {:>10} {:>10} {:>12}  File", "#", "Lifespan", "Population", "Bounding box");
+	for (position, candidate) in candidates.iter().take(options.top).enumerate() {
+		let path = format!("{}/methuselah_{}.rle", options.output, position + 1);
+		let lifespan = if candidate.stabilised { candidate.lifespan.to_string() } else { format!(">{}", candidate.lifespan) };
+		let comments = vec![
+			match candidate.stabilised {
+				true => format!("Lifespan: {} generations", candidate.lifespan),
+				false => format!("Lifespan: didn't stabilise within {} generations", candidate.lifespan)
+			},
+			format!("Final population: {}", candidate.final_population),
+			format!("Maximum bounding box: {}x{}", candidate.max_bounding_box.0, candidate.max_bounding_box.1)
+		];
+		let pattern = Pattern {
+			width: options.width,
+			height: options.height,
+			cells: candidate.cells.clone(),
+			states: Vec::new(),
+			rule: None
+		};
+		rle::save(path.as_str(), &pattern, &comments)?;
+		println!("{:>4} {:>10} {:>10} {:>12}  {}", position + 1, lifespan, candidate.final_population, format!("{}x{}", candidate.max_bounding_box.0, candidate.max_bounding_box.1), path);
+	}
+	Ok(())
+}
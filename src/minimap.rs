@@ -0,0 +1,147 @@
+extern crate sdl2;
+extern crate rayon;
+
+
+use rayon::iter::IndexedParallelIterator;
+use rayon::iter::IntoParallelRefMutIterator;
+use rayon::iter::ParallelIterator;
+use sdl2::rect::Point;
+use sdl2::rect::Rect;
+
+use crate::SUB_MATRIX_CHUNK_SIZE;
+
+
+const MINIMAP_SIZE: u32 = crate::SIDE_PANEL_WIDTH; // Width and height of the minimap, in pixels
+const CELLS_PER_PIXEL: u32 = crate::MATRIX_SIZE / MINIMAP_SIZE; // Width and height of the block of cells that each minimap pixel represents
+const VIEWPORT_MIN_SIZE: u32 = 4; // Minimum size of the viewport rectangle, so it's still visible on big matrices
+
+
+// Downscaled view of the whole matrix. Each pixel is lit if any cell of its block is alive
+pub struct Minimap {
+	rect: Rect,
+	occupancy: Vec<Vec<bool>>
+}
+
+impl Minimap {
+	pub fn new(x: i32, y: i32) -> Self {
+		Self {
+			rect: Rect::new(x, y, MINIMAP_SIZE, MINIMAP_SIZE),
+			occupancy: vec![vec![false; MINIMAP_SIZE as usize]; MINIMAP_SIZE as usize]
+		}
+	}
+
+	pub fn clear(&mut self) {
+		self.occupancy = vec![vec![false; MINIMAP_SIZE as usize]; MINIMAP_SIZE as usize];
+	}
+
+	fn block_has_alive_cells(generation: &[Vec<bool>], block_row: usize, block_col: usize) -> bool {
+		let cells = CELLS_PER_PIXEL as usize;
+		generation[block_row * cells..(block_row + 1) * cells].iter()
+			.any(|row| row[block_col * cells..(block_col + 1) * cells].contains(&true))
+	}
+
+	// Recalculates every block. Expensive, only used when the generation is replaced as a whole
+	pub fn refresh_all(&mut self, generation: &[Vec<bool>]) {
+		self.occupancy.par_iter_mut().enumerate().for_each(|(block_row, row_content)| {
+			for (block_col, elem) in row_content.iter_mut().enumerate() {
+				*elem = Minimap::block_has_alive_cells(generation, block_row, block_col);
+			}
+		});
+	}
+
+	// Recalculates only the blocks covered by chunks that changed in the last iteration
+	pub fn refresh(&mut self, generation: &[Vec<bool>], change_matrix: &[Vec<bool>]) {
+		for (sub_row, changed_row) in change_matrix.iter().enumerate() {
+			for (sub_col, &changed) in changed_row.iter().enumerate() {
+				if !changed {
+					continue;
+				}
+
+				let from_row = sub_row * SUB_MATRIX_CHUNK_SIZE as usize / CELLS_PER_PIXEL as usize;
+				let to_row = ((sub_row + 1) * SUB_MATRIX_CHUNK_SIZE as usize).div_ceil(CELLS_PER_PIXEL as usize);
+				let from_col = sub_col * SUB_MATRIX_CHUNK_SIZE as usize / CELLS_PER_PIXEL as usize;
+				let to_col = ((sub_col + 1) * SUB_MATRIX_CHUNK_SIZE as usize).div_ceil(CELLS_PER_PIXEL as usize);
+				for block_row in from_row..to_row {
+					for block_col in from_col..to_col {
+						self.occupancy[block_row][block_col] = Minimap::block_has_alive_cells(generation, block_row, block_col);
+					}
+				}
+			}
+		}
+	}
+
+	// Updates the block of a manually edited cell. Erasing needs the whole block to be checked
+	pub fn set_cell(&mut self, generation: &[Vec<bool>], row: usize, col: usize) {
+		let block_row = row / CELLS_PER_PIXEL as usize;
+		let block_col = col / CELLS_PER_PIXEL as usize;
+		self.occupancy[block_row][block_col] = generation[row][col] || Minimap::block_has_alive_cells(generation, block_row, block_col);
+	}
+
+	pub fn contains(&self, x: i32, y: i32) -> bool {
+		self.rect.contains_point(Point::new(x, y))
+	}
+
+	// Converts a point inside the minimap to the matrix (row, col) it represents
+	pub fn get_cell(&self, x: i32, y: i32) -> (u32, u32) {
+		let block_row = (y - self.rect.y).clamp(0, MINIMAP_SIZE as i32 - 1) as u32;
+		let block_col = (x - self.rect.x).clamp(0, MINIMAP_SIZE as i32 - 1) as u32;
+		(block_row * CELLS_PER_PIXEL + CELLS_PER_PIXEL / 2, block_col * CELLS_PER_PIXEL + CELLS_PER_PIXEL / 2)
+	}
+
+	pub fn draw(&self, canvas: &mut sdl2::render::Canvas<sdl2::video::Window>, view_center_row: u32, view_center_col: u32) {
+		canvas.set_draw_color(crate::COLOR_BLACK_1);
+		let _ = canvas.fill_rect(self.rect);
+
+		let points: Vec<Point> = self.occupancy.iter().enumerate().flat_map(|(block_row, row_content)| {
+			row_content.iter().enumerate()
+				.filter(|(_, occupied)| **occupied)
+				.map(move |(block_col, _)| Point::new(self.rect.x + block_col as i32, self.rect.y + block_row as i32))
+		}).collect();
+		canvas.set_draw_color(crate::COLOR_WHITE);
+		let _ = canvas.draw_points(points.as_slice());
+
+		// Current viewport
+		let view_width = u32::max(crate::VIEW_COLS / CELLS_PER_PIXEL, VIEWPORT_MIN_SIZE);
+		let view_height = u32::max(crate::VIEW_ROWS / CELLS_PER_PIXEL, VIEWPORT_MIN_SIZE);
+		let view_center_x = self.rect.x + (view_center_col / CELLS_PER_PIXEL) as i32;
+		let view_center_y = self.rect.y + (view_center_row / CELLS_PER_PIXEL) as i32;
+		canvas.set_draw_color(crate::COLOR_RED);
+		let _ = canvas.draw_rect(Rect::from_center(Point::new(view_center_x, view_center_y), view_width, view_height));
+
+		canvas.set_draw_color(crate::COLOR_BLACK_3);
+		let _ = canvas.draw_rect(self.rect);
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn get_cell_is_the_middle_of_the_block_of_the_pixel() {
+		let minimap = Minimap::new(100, 50);
+		assert_eq!(minimap.get_cell(100, 50), (CELLS_PER_PIXEL / 2, CELLS_PER_PIXEL / 2));
+		assert_eq!(minimap.get_cell(103, 52), (2 * CELLS_PER_PIXEL + CELLS_PER_PIXEL / 2, 3 * CELLS_PER_PIXEL + CELLS_PER_PIXEL / 2));
+
+		// Dragging past the edges stays on the last blocks
+		assert_eq!(minimap.get_cell(0, 10000), (crate::MATRIX_SIZE - CELLS_PER_PIXEL / 2, CELLS_PER_PIXEL / 2));
+		assert!(minimap.contains(100, 50) && !minimap.contains(99, 50));
+	}
+
+	#[test]
+	fn set_cell_updates_its_block() {
+		let mut minimap = Minimap::new(0, 0);
+		let mut generation = vec![vec![false; crate::MATRIX_SIZE as usize]; crate::MATRIX_SIZE as usize];
+		let (row, col) = (3 * CELLS_PER_PIXEL as usize + 1, 156 * CELLS_PER_PIXEL as usize + 2);
+
+		generation[row][col] = true;
+		minimap.set_cell(&generation, row, col);
+		assert!(minimap.occupancy[3][156]);
+		assert_eq!(minimap.occupancy.iter().flatten().filter(|occupied| **occupied).count(), 1);
+
+		generation[row][col] = false;
+		minimap.set_cell(&generation, row, col);
+		assert!(!minimap.occupancy[3][156]);
+	}
+}
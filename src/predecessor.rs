@@ -0,0 +1,188 @@
+use std::collections::HashSet;
+
+use crate::rle;
+use crate::rle::Pattern;
+use crate::rule;
+use crate::rule::Rule;
+
+
+pub const MAX_SIDE: u32 = 8; // The shortest side of the target can't be longer, the search grows exponentially with it
+
+const USAGE: &str = "Usage: conway --predecessor <pattern.rle> [--output <parent.rle>]";
+
+
+// Cells of the target area that must be alive after one generation, each row as a bitmask
+struct Target {
+	width: u32,
+	height: u32,
+	rows: Vec<u32>
+}
+
+struct Search<'t> {
+	target: &'t Target,
+	rule: &'t Rule,
+	candidates: Vec<u32>, // Every possible row for the first two parent rows, the sparsest first
+	parent: Vec<u32>,
+	dead_ends: HashSet<(usize, u32, u32)> // (row, previous two rows) that are known to lead nowhere
+}
+
+impl<'t> Search<'t> {
+	// Whether the cell of the target row gets the right state from the parent rows
+	fn cell_matches(&self, above: u32, row: u32, below: u32, target_row: u32, col: u32) -> bool {
+		let alive = row & (1 << (col + 1)) != 0;
+		let mut neighborhood = 0;
+		for (bit, (row_offset, col_offset)) in rule::NEIGHBORS.iter().enumerate() {
+			let parent_row = match row_offset {
+				-1 => above,
+				0 => row,
+				_ => below
+			};
+			if parent_row & (1 << (col as i32 + 1 + col_offset)) != 0 {
+				neighborhood |= 1 << bit;
+			}
+		}
+		self.rule.next(alive as u8, neighborhood) == (target_row & (1 << col) != 0) as u8
+	}
+
+	// Parent rows that can go below the other two, built one column at a time so wrong ones are discarded early
+	fn next_rows(&self, above: u32, row: u32, target_row: u32) -> Vec<u32> {
+		let mut rows = vec![0b00, 0b01, 0b10, 0b11];
+		for col in 0..self.target.width {
+			let bit = 1 << (col + 2);
+			rows = rows.into_iter()
+				.flat_map(|below| [below, below | bit])
+				.filter(|below| self.cell_matches(above, row, *below, target_row, col))
+				.collect();
+		}
+		rows
+	}
+
+	// Depth first search of the parent rows, one row at a time. Each row completes the neighborhoods of a target row
+	fn extend(&mut self) -> bool {
+		let depth = self.parent.len();
+		if depth == self.target.height as usize + 2 {
+			return true;
+		}
+
+		let candidates = match depth {
+			0 | 1 => self.candidates.clone(),
+			_ => {
+				let key = (depth, self.parent[depth - 2], self.parent[depth - 1]);
+				if self.dead_ends.contains(&key) {
+					return false;
+				}
+				self.next_rows(key.1, key.2, self.target.rows[depth - 2])
+			}
+		};
+
+		for candidate in candidates {
+			self.parent.push(candidate);
+			if self.extend() {
+				return true;
+			}
+			self.parent.pop();
+		}
+
+		if depth >= 2 {
+			self.dead_ends.insert((depth, self.parent[depth - 2], self.parent[depth - 1]));
+		}
+		false
+	}
+}
+
+// Searches a generation that evolves into the cells (and the dead cells) of the target area. Only the cells up to one away
+// from the area can affect it, so if there is no parent in that box there is none at all: the area is a Garden of Eden.
+// The parent is returned relative to the top left corner of the area, shifted by one cell
+pub fn find(width: u32, height: u32, cells: &[(u32, u32)], rule: &Rule) -> Result<Option<Pattern>, String> {
+	if rule.table.is_some() {
+		return Err(format!("{} is a rule table, only predecessors in Life-like rules can be searched", rule));
+	}
+	if rule.block.is_some() {
+		return Err(format!("{} is a block rule, only predecessors in Life-like rules can be searched", rule));
+	}
+	if rule.range.is_some() {
+		return Err(format!("{} is a Larger than Life rule, only predecessors in rules on the 8 neighbors can be searched", rule));
+	}
+	if rule.states > 2 {
+		return Err(format!("{} has more than 2 states, only predecessors in two-state rules can be searched", rule));
+	}
+	if u32::min(width, height) > MAX_SIDE {
+		return Err(format!("the {}x{} area is too big, the shortest side can't be longer than {} cells", width, height, MAX_SIDE));
+	}
+	if width == 0 || height == 0 {
+		return Err("the area is empty".to_string());
+	}
+
+	// The rule is the same for every rotation, so the search goes along the longest side to keep the rows short
+	let transposed = width > height;
+	let (target_width, target_height) = if transposed { (height, width) } else { (width, height) };
+	let mut rows = vec![0; target_height as usize];
+	for (row, col) in cells {
+		let (row, col) = if transposed { (*col, *row) } else { (*row, *col) };
+		rows[row as usize] |= 1 << col;
+	}
+	let target = Target {
+		width: target_width,
+		height: target_height,
+		rows
+	};
+
+	let mut candidates: Vec<u32> = (0..1u32 << (target_width + 2)).collect();
+	candidates.sort_by_key(|candidate| candidate.count_ones());
+	let mut search = Search {
+		target: &target,
+		rule,
+		candidates,
+		parent: Vec::new(),
+		dead_ends: HashSet::new()
+	};
+	if !search.extend() {
+		return Ok(None);
+	}
+
+	let mut parent_cells = Vec::new();
+	for (row, mask) in search.parent.iter().enumerate() {
+		for col in 0..target_width + 2 {
+			if mask & (1 << col) != 0 {
+				parent_cells.push(if transposed { (col, row as u32) } else { (row as u32, col) });
+			}
+		}
+	}
+	Ok(Some(Pattern {
+		width: width + 2,
+		height: height + 2,
+		cells: parent_cells,
+		states: Vec::new(),
+		rule: Some(rule.to_string())
+	}))
+}
+
+// Headless predecessor search of the whole area of an RLE pattern
+pub fn run(args: &[String]) -> Result<(), String> {
+	let mut pattern_path = None;
+	let mut output_path = None;
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--predecessor" => {},
+			"--output" => output_path = Some(args.next().ok_or(format!("missing value for --output\n{}", USAGE))?.to_string()),
+			_ if arg.starts_with("--") => return Err(format!("unknown option \"{}\"\n{}", arg, USAGE)),
+			_ => pattern_path = Some(arg.to_string())
+		}
+	}
+
+	let pattern = rle::load(pattern_path.ok_or(format!("missing pattern file\n{}", USAGE))?.as_str())?;
+	match find(pattern.width, pattern.height, &pattern.cells, &pattern.parsed_rule()?)? {
+		Some(parent) => {
+			println!("Predecessor found:");
+			let comments = vec![format!("Predecessor of the {}x{} pattern, shifted one cell up and left", pattern.width, pattern.height)];
+			print!("{}", rle::to_string(&parent, &comments));
+			if let Some(output_path) = output_path {
+				rle::save(output_path.as_str(), &parent, &comments)?;
+				println!("Saved to {}", output_path);
+			}
+		},
+		None => println!("No predecessor exists: the {}x{} pattern is a Garden of Eden", pattern.width, pattern.height)
+	}
+	Ok(())
+}
@@ -0,0 +1,275 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+
+use crate::engine::BoundingBox;
+use crate::engine::Engine;
+use crate::image_export;
+use crate::image_export::Image;
+use crate::image_export::Scale;
+use crate::rle;
+use crate::rle::Pattern;
+
+
+const DEFAULT_GENERATIONS: i32 = 100;
+const DEFAULT_DELAY: u32 = 100;
+const FIT_FRAME_SIDE: u32 = 800; // Widest or tallest frame when the scale is chosen automatically
+
+const USAGE: &str = "Usage: conway --record <pattern.rle> --output <run.gif|run.y4m|-|directory> [--from <generation>] [--to <generation>]
+                        [--region <row>,<col>,<width>,<height>] [--delay <milliseconds>] [--scale <pixels per cell|1/cells per pixel>] [--grid]
+The output is an animated GIF, a Y4M stream (- writes it to the standard output, e.g. to pipe it into ffmpeg) or a directory of numbered PNG frames.
+The region is relative to the top left corner of the pattern, by default it's everything the pattern reaches during the recorded generations";
+
+
+enum Format {
+	Gif,
+	Y4m,
+	PngSequence
+}
+
+struct Options {
+	pattern_path: String,
+	output: String,
+	format: Format,
+	from: i32,
+	to: i32,
+	region: Option<(i64, i64, u32, u32)>, // (row, col, width, height) relative to the pattern
+	delay: u32, // Milliseconds between frames
+	scale: Option<Scale>,
+	grid: bool
+}
+
+fn parse_value<T: std::str::FromStr>(args: &mut std::slice::Iter<String>, option: &str) -> Result<T, String> {
+	let value = args.next().ok_or(format!("missing value for {}\n{}", option, USAGE))?;
+	value.parse::<T>().map_err(|_| format!("invalid value \"{}\" for {}", value, option))
+}
+
+fn parse_region(text: &str) -> Result<(i64, i64, u32, u32), String> {
+	let values: Vec<&str> = text.split(',').map(|value| value.trim()).collect();
+	let region = match values[..] {
+		[row, col, width, height] => (row.parse::<i64>(), col.parse::<i64>(), width.parse::<u32>(), height.parse::<u32>()),
+		_ => return Err(format!("invalid region \"{}\", expected <row>,<col>,<width>,<height>", text))
+	};
+	match region {
+		(Ok(row), Ok(col), Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((row, col, width, height)),
+		_ => Err(format!("invalid region \"{}\", expected <row>,<col>,<width>,<height>", text))
+	}
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+	let mut pattern_path = None;
+	let mut output = None;
+	let mut options = Options {
+		pattern_path: String::new(),
+		output: String::new(),
+		format: Format::PngSequence,
+		from: 0,
+		to: DEFAULT_GENERATIONS,
+		region: None,
+		delay: DEFAULT_DELAY,
+		scale: None,
+		grid: false
+	};
+
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--record" => {},
+			"--output" => output = Some(parse_value::<String>(&mut args, arg)?),
+			"--from" => options.from = parse_value(&mut args, arg)?,
+			"--to" => options.to = parse_value(&mut args, arg)?,
+			"--region" => options.region = Some(parse_region(parse_value::<String>(&mut args, arg)?.as_str())?),
+			"--delay" => options.delay = parse_value(&mut args, arg)?,
+			"--scale" => options.scale = Some(Scale::parse(parse_value::<String>(&mut args, arg)?.as_str())?),
+			"--grid" => options.grid = true,
+			_ if arg.starts_with("--") => return Err(format!("unknown option \"{}\"\n{}", arg, USAGE)),
+			_ => pattern_path = Some(arg.to_string())
+		}
+	}
+
+	options.pattern_path = pattern_path.ok_or(format!("missing pattern file\n{}", USAGE))?;
+	options.output = output.ok_or(format!("missing --output\n{}", USAGE))?;
+	let extension = options.output.to_lowercase();
+	options.format = if extension.ends_with(".gif") {
+		Format::Gif
+	} else if extension.ends_with(".y4m") || options.output == "-" {
+		Format::Y4m
+	} else {
+		Format::PngSequence
+	};
+	if options.from < 0 || options.to < options.from {
+		return Err(format!("invalid generations {}..{}, --to can't be before --from", options.from, options.to));
+	}
+	if options.delay == 0 {
+		return Err("the delay must be at least 1 millisecond".to_string());
+	}
+	Ok(options)
+}
+
+// Universe with the pattern in its middle, and the position of its top left corner
+fn load(pattern: &Pattern) -> Result<(Engine, u32, u32), String> {
+	let mut engine = Engine::new();
+	engine.set_rule(pattern.parsed_rule()?);
+	let (top, left) = (crate::MATRIX_SIZE.saturating_sub(pattern.height) / 2, crate::MATRIX_SIZE.saturating_sub(pattern.width) / 2);
+	engine.load_pattern(pattern, top, left)?;
+	Ok((engine, top, left))
+}
+
+fn run_until(engine: &mut Engine, generation: i32) {
+	while engine.generation_number() < generation {
+		engine.iterate();
+	}
+}
+
+// Bounding box of every generation from the first to the last one, so no frame is cropped
+fn reached_region(pattern: &Pattern, from: i32, to: i32) -> Result<BoundingBox, String> {
+	let (mut engine, ..) = load(pattern)?;
+	run_until(&mut engine, from);
+	let mut region: Option<BoundingBox> = None;
+	loop {
+		if let Some(bounding_box) = engine.bounding_box() {
+			BoundingBox::include(&mut region, bounding_box.top, bounding_box.left);
+			BoundingBox::include(&mut region, bounding_box.bottom, bounding_box.right);
+		}
+		if engine.generation_number() >= to {
+			break;
+		}
+		engine.iterate();
+	}
+	region.ok_or("the pattern is empty during every recorded generation".to_string())
+}
+
+// Writes the frames in one of the formats as they are rendered, so long runs don't have to be kept in memory
+enum Recorder {
+	Gif(gif::Encoder<BufWriter<File>>),
+	Y4m(Box<dyn Write>),
+	PngSequence(String)
+}
+
+impl Recorder {
+	fn new(options: &Options, width: u32, height: u32) -> Result<Self, String> {
+		let path = options.output.as_str();
+		match options.format {
+			Format::Gif => {
+				if width > u16::MAX as u32 || height > u16::MAX as u32 {
+					return Err(format!("the {}x{} frames are too big for a GIF", width, height));
+				}
+				let file = File::create(path).map_err(|error| format!("could not create {}: {}", path, error))?;
+				let mut encoder = gif::Encoder::new(BufWriter::new(file), width as u16, height as u16, &[]).map_err(|error| format!("could not write {}: {}", path, error))?;
+				encoder.set_repeat(gif::Repeat::Infinite).map_err(|error| format!("could not write {}: {}", path, error))?;
+				Ok(Recorder::Gif(encoder))
+			},
+			Format::Y4m => {
+				let mut writer: Box<dyn Write> = match path {
+					"-" => Box::new(BufWriter::new(std::io::stdout())),
+					_ => Box::new(BufWriter::new(File::create(path).map_err(|error| format!("could not create {}: {}", path, error))?))
+				};
+				// Frame rate as a fraction of frames per second, with full resolution chroma so any size works
+				writeln!(writer, "YUV4MPEG2 W{} H{} F1000:{} Ip A1:1 C444", width, height, options.delay).map_err(|error| format!("could not write {}: {}", path, error))?;
+				Ok(Recorder::Y4m(writer))
+			},
+			Format::PngSequence => {
+				fs::create_dir_all(path).map_err(|error| format!("could not create {}: {}", path, error))?;
+				Ok(Recorder::PngSequence(path.to_string()))
+			}
+		}
+	}
+
+	fn write(&mut self, image: &Image, generation: i32, delay: u32) -> Result<(), String> {
+		match self {
+			Recorder::Gif(encoder) => {
+				// The theme only has a few colors, so each frame gets its own exact palette instead of a quantized one
+				let mut palette: HashMap<&[u8], u8> = HashMap::new();
+				let mut colors = Vec::new();
+				let mut indices = Vec::with_capacity((image.width * image.height) as usize);
+				for pixel in image.pixels.chunks(3) {
+					let index = match palette.get(pixel) {
+						Some(index) => *index,
+						None if palette.len() < 256 => {
+							colors.extend_from_slice(pixel);
+							palette.insert(pixel, palette.len() as u8);
+							(palette.len() - 1) as u8
+						},
+						None => return Err("the frame has more than 256 colors".to_string())
+					};
+					indices.push(index);
+				}
+
+				let frame = gif::Frame {
+					width: image.width as u16,
+					height: image.height as u16,
+					buffer: Cow::Owned(indices),
+					palette: Some(colors),
+					delay: (delay / 10).clamp(1, u16::MAX as u32) as u16, // In hundredths of a second
+					..gif::Frame::default()
+				};
+				encoder.write_frame(&frame).map_err(|error| format!("could not write the frame of generation {}: {}", generation, error))
+			},
+			Recorder::Y4m(writer) => {
+				// BT.601 full range, one plane after the other
+				let mut planes = vec![0; image.pixels.len()];
+				let area = (image.width * image.height) as usize;
+				for (index, pixel) in image.pixels.chunks(3).enumerate() {
+					let (r, g, b) = (pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+					planes[index] = (0.299 * r + 0.587 * g + 0.114 * b).round() as u8;
+					planes[area + index] = (128.0 - 0.168736 * r - 0.331264 * g + 0.5 * b).round().clamp(0.0, 255.0) as u8;
+					planes[area * 2 + index] = (128.0 + 0.5 * r - 0.418688 * g - 0.081312 * b).round().clamp(0.0, 255.0) as u8;
+				}
+				writer.write_all(b"FRAME\n").and_then(|_| writer.write_all(&planes)).map_err(|error| format!("could not write the frame of generation {}: {}", generation, error))
+			},
+			Recorder::PngSequence(directory) => image.save_png(format!("{}/frame_{:06}.png", directory, generation).as_str())
+		}
+	}
+
+	fn finish(self) -> Result<(), String> {
+		match self {
+			Recorder::Gif(encoder) => encoder.into_inner().and_then(|mut writer| writer.flush()).map_err(|error| format!("could not finish the GIF: {}", error)),
+			Recorder::Y4m(mut writer) => writer.flush().map_err(|error| format!("could not finish the stream: {}", error)),
+			Recorder::PngSequence(_) => Ok(())
+		}
+	}
+}
+
+// Records a range of generations of a pattern, cropped to a region, as an animation or a sequence of frames
+pub fn run(args: &[String]) -> Result<(), String> {
+	let options = parse_options(args)?;
+	let pattern = rle::load(options.pattern_path.as_str())?;
+	// The standard output may be the video stream, so progress is reported on the error output
+	let log = |message: String| if options.output == "-" { eprintln!("{}", message) } else { println!("{}", message) };
+
+	let (mut engine, top, left) = load(&pattern)?;
+	let region = match options.region {
+		Some((row, col, width, height)) => {
+			let (region_top, region_left) = (top as i64 + row, left as i64 + col);
+			if region_top < 0 || region_left < 0 || region_top + height as i64 > crate::MATRIX_SIZE as i64 || region_left + width as i64 > crate::MATRIX_SIZE as i64 {
+				return Err(format!("the region {},{} {}x{} is outside of the universe", row, col, width, height));
+			}
+			BoundingBox {
+				top: region_top as u32,
+				left: region_left as u32,
+				bottom: (region_top + height as i64 - 1) as u32,
+				right: (region_left + width as i64 - 1) as u32
+			}
+		},
+		None => reached_region(&pattern, options.from, options.to)?
+	};
+	let scale = options.scale.unwrap_or(Scale::fit(region, FIT_FRAME_SIDE));
+
+	run_until(&mut engine, options.from);
+	let mut recorder = Recorder::new(&options, scale.pixels(region.width()), scale.pixels(region.height()))?;
+	loop {
+		let image = image_export::render(&engine, region, scale, options.grid)?;
+		recorder.write(&image, engine.generation_number(), options.delay)?;
+		if engine.generation_number() >= options.to {
+			break;
+		}
+		engine.iterate();
+	}
+	recorder.finish()?;
+
+	log(format!("Recorded generations {} to {} ({}x{} cells) into {}", options.from, options.to, region.width(), region.height(), options.output));
+	Ok(())
+}
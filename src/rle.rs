@@ -0,0 +1,238 @@
+use std::fs;
+
+use crate::rule::Rule;
+use crate::rule_table;
+
+
+// Alive cells of a pattern, relative to its top left corner
+pub struct Pattern {
+	pub width: u32,
+	pub height: u32,
+	pub cells: Vec<(u32, u32)>, // (row, col)
+	pub states: Vec<(u32, u32, u8)>, // (row, col, state) of the cells in a dying state, for Generations rules
+	pub rule: Option<String>
+}
+
+impl Pattern {
+	// The rule of the header, B3/S23 if there is none. Names that aren't rulestrings are looked for as .rule files
+	pub fn parsed_rule(&self) -> Result<Rule, String> {
+		match self.rule.as_ref() {
+			Some(rule) => rule_table::parse_rule(rule.as_str()),
+			None => Ok(Rule::life())
+		}
+	}
+}
+
+pub fn load(path: &str) -> Result<Pattern, String> {
+	let text = fs::read_to_string(path).map_err(|error| format!("could not read {}: {}", path, error))?;
+	parse(text.as_str())
+}
+
+// Writes the pattern in the Run Length Encoded format, each comment on its own #C line
+pub fn save(path: &str, pattern: &Pattern, comments: &[String]) -> Result<(), String> {
+	fs::write(path, to_string(pattern, comments)).map_err(|error| format!("could not write {}: {}", path, error))
+}
+
+// Patterns with more than 2 states use the multi-state alphabet: '.' is dead, 'A' alive, and the next letters the other states
+pub fn to_string(pattern: &Pattern, comments: &[String]) -> String {
+	let mut text = String::new();
+	for comment in comments {
+		text += format!("#C {}\n", comment).as_str();
+	}
+	text += format!("x = {}, y = {}, rule = {}\n", pattern.width, pattern.height, pattern.rule.as_deref().unwrap_or("B3/S23")).as_str();
+
+	let multi_state = !pattern.states.is_empty() || pattern.parsed_rule().is_ok_and(|rule| rule.states > 2);
+	let mut rows = vec![Vec::new(); pattern.height as usize];
+	for (row, col, state) in pattern.cells.iter().map(|(row, col)| (*row, *col, 1)).chain(pattern.states.iter().copied()) {
+		rows[row as usize].push((col, state));
+	}
+
+	// Runs of (count, tag), dead cells at the end of a row and empty rows are merged into the next '$'
+	let mut runs: Vec<(u32, String)> = Vec::new();
+	let mut push_run = |count: u32, tag: String| {
+		match runs.last_mut() {
+			Some((last_count, last_tag)) if *last_tag == tag => *last_count += count,
+			_ => runs.push((count, tag))
+		}
+	};
+	let dead = if multi_state { "." } else { "b" };
+	for (index, cells) in rows.iter_mut().enumerate() {
+		if index > 0 {
+			push_run(1, "$".to_string());
+		}
+		cells.sort();
+		let mut col = 0;
+		for (alive_col, state) in cells.iter() {
+			if *alive_col > col {
+				push_run(alive_col - col, dead.to_string());
+			}
+			push_run(1, if multi_state { state_tag(*state) } else { "o".to_string() });
+			col = alive_col + 1;
+		}
+	}
+	while runs.last().is_some_and(|(_, tag)| tag == "$") {
+		runs.pop();
+	}
+	runs.push((1, "!".to_string()));
+
+	// Lines shouldn't exceed 70 characters
+	let mut line = String::new();
+	for (count, tag) in runs {
+		let run = if count == 1 { tag } else { format!("{}{}", count, tag) };
+		if line.len() + run.len() > 70 {
+			text += format!("{}\n", line).as_str();
+			line.clear();
+		}
+		line += run.as_str();
+	}
+	text += format!("{}\n", line).as_str();
+	text
+}
+
+// Letter of a state in the multi-state alphabet, A to X preceded by p to y for the states above 24
+fn state_tag(state: u8) -> String {
+	let letter = (b'A' + (state - 1) % 24) as char;
+	match (state - 1) / 24 {
+		0 => letter.to_string(),
+		prefix => format!("{}{}", (b'p' + prefix - 1) as char, letter)
+	}
+}
+
+// Parses the Run Length Encoded format (https://conwaylife.com/wiki/Run_Length_Encoded)
+pub fn parse(text: &str) -> Result<Pattern, String> {
+	let mut width = 0;
+	let mut height = 0;
+	let mut rule = None;
+	let mut cells = Vec::new();
+	let mut states = Vec::new();
+
+	let mut header_found = false;
+	let mut row: u32 = 0;
+	let mut col: u32 = 0;
+	let mut run_count = String::new();
+	let mut state_prefix: Option<char> = None;
+
+	'lines: for line in text.lines() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		if !header_found {
+			// The rule is the last field, and may have commas of its own (R5,C0,M1,S34..58,B34..45,NM)
+			let (fields, rule_field) = match line.find("rule") {
+				Some(index) => (line[..index].trim_end().trim_end_matches(','), Some(&line[index..])),
+				None => (line, None)
+			};
+			for field in fields.split(',').chain(rule_field) {
+				let (key, value) = match field.split_once('=') {
+					Some((key, value)) => (key.trim(), value.trim()),
+					None => return Err(format!("invalid header field \"{}\"", field.trim()))
+				};
+				match key {
+					"x" => width = value.parse::<u32>().map_err(|_| format!("invalid width \"{}\"", value))?,
+					"y" => height = value.parse::<u32>().map_err(|_| format!("invalid height \"{}\"", value))?,
+					"rule" => rule = Some(value.to_string()),
+					_ => {}
+				}
+			}
+			header_found = true;
+			continue;
+		}
+
+		let mut characters = line.chars().peekable();
+		while let Some(character) = characters.next() {
+			if character.is_ascii_digit() {
+				run_count.push(character);
+				continue;
+			}
+			// Multi-state cells above 24 are written as a lowercase prefix (p to y) and a letter
+			if ('p'..='y').contains(&character) && characters.peek().is_some_and(|next| next.is_ascii_uppercase()) {
+				state_prefix = Some(character);
+				continue;
+			}
+
+			let count = if run_count.is_empty() { 1 } else { run_count.parse::<u32>().map_err(|_| format!("invalid run count \"{}\"", run_count))? };
+			run_count.clear();
+			match character {
+				'b' | '.' => col += count,
+				'$' => {
+					row += count;
+					col = 0;
+				},
+				'!' => break 'lines,
+				// Multi-state cells: A is alive, B and the next letters are states above it
+				character if character.is_ascii_uppercase() && (character as u8 - b'A' < 24) => {
+					let state = state_prefix.take().map_or(0, |prefix| (prefix as u32 - 'p' as u32 + 1) * 24) + (character as u32 - 'A' as u32) + 1;
+					let state = u8::try_from(state).map_err(|_| format!("invalid state {}", state))?;
+					for _ in 0..count {
+						match state {
+							1 => cells.push((row, col)),
+							_ => states.push((row, col, state))
+						}
+						col += 1;
+					}
+				},
+				character if character.is_ascii_alphabetic() => {
+					for _ in 0..count {
+						cells.push((row, col));
+						col += 1;
+					}
+				},
+				character if character.is_whitespace() => {},
+				character => return Err(format!("unexpected character '{}'", character))
+			}
+		}
+	}
+
+	if !header_found {
+		return Err("missing \"x = ..., y = ...\" header".to_string());
+	}
+
+	// Be lenient with headers smaller than the actual pattern
+	for (row, col) in cells.iter().copied().chain(states.iter().map(|(row, col, _)| (*row, *col))) {
+		width = u32::max(width, col + 1);
+		height = u32::max(height, row + 1);
+	}
+
+	Ok(Pattern {
+		width,
+		height,
+		cells,
+		states,
+		rule
+	})
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_two_states() {
+		let pattern = parse("#N Glider\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!").unwrap();
+		assert_eq!((pattern.width, pattern.height, pattern.rule.as_deref()), (3, 3, Some("B3/S23")));
+		assert_eq!(pattern.cells, vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]);
+		assert!(pattern.states.is_empty());
+	}
+
+	#[test]
+	fn to_string_two_states() {
+		let pattern = parse("x = 3, y = 3, rule = B3/S23\nbob$2bo$3o!").unwrap();
+		assert_eq!(to_string(&pattern, &["Glider".to_string()]), "#C Glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n");
+	}
+
+	#[test]
+	fn parse_multi_state_letters() {
+		let pattern = parse("x = 3, y = 2, rule = B2/S/C30\n.AB$CpA!").unwrap();
+		assert_eq!(pattern.cells, vec![(0, 1)]);
+		assert_eq!(pattern.states, vec![(0, 2, 2), (1, 0, 3), (1, 1, 25)]);
+	}
+
+	#[test]
+	fn multi_state_round_trip() {
+		let text = "x = 3, y = 2, rule = B2/S/C30\n.AB$CpA!\n";
+		assert_eq!(to_string(&parse(text).unwrap(), &[]), text);
+	}
+}
@@ -0,0 +1,681 @@
+use core::fmt;
+use std::sync::Arc;
+
+use crate::rule_table::RuleTable;
+
+
+// Neighbors of a cell as (row, col) offsets, clockwise from the one above. Bit i of a neighborhood is whether the i-th is alive
+pub const NEIGHBORS: [(i32, i32); 8] = [(-1, 0), (-1, 1), (0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1)];
+
+// Hensel notation: the letter of each isotropic neighborhood with 1 to 4 alive neighbors, and one of its bitmasks. Those with 5
+// to 7 neighbors share the letters of 3 to 1, with the alive and dead neighbors swapped
+const HENSEL_LETTERS: [&str; 5] = ["", "ce", "cekain", "cekainyqjr", "cekainyqjrtwz"];
+const HENSEL_NEIGHBORHOODS: [&[u8]; 5] = [
+	&[],
+	&[0x02, 0x01],
+	&[0x0a, 0x05, 0x09, 0x03, 0x11, 0x22],
+	&[0x2a, 0x15, 0x25, 0x07, 0x83, 0x0b, 0x29, 0x23, 0x43, 0x13],
+	&[0xaa, 0x55, 0x4b, 0x0f, 0x1b, 0x8b, 0x2b, 0x27, 0x53, 0x17, 0x39, 0x63, 0x33]
+];
+
+pub const MAX_RADIUS: u8 = 10; // Widest range of Larger than Life rules, it must stay below SUB_MATRIX_CHUNK_SIZE
+
+fn hensel_letters(count: usize) -> &'static str {
+	HENSEL_LETTERS[usize::min(count, 8 - count)]
+}
+
+// The neighborhood rotated and mirrored in the 8 possible ways
+fn symmetries(neighborhood: u8) -> [u8; 8] {
+	let mut symmetries = [0; 8];
+	for rotation in 0..4 {
+		let rotated = neighborhood.rotate_left(rotation * 2);
+		symmetries[rotation as usize * 2] = rotated;
+		symmetries[rotation as usize * 2 + 1] = rotated.reverse_bits().rotate_left(1); // Mirrored along the vertical axis
+	}
+	symmetries
+}
+
+// Letter of the neighborhood in Hensel notation, None with 0 or 8 alive neighbors
+fn hensel_letter(neighborhood: u8) -> Option<char> {
+	let count = neighborhood.count_ones() as usize;
+	let (representatives, swapped) = match count {
+		0..=4 => (HENSEL_NEIGHBORHOODS[count], false),
+		_ => (HENSEL_NEIGHBORHOODS[8 - count], true)
+	};
+	let index = representatives.iter().position(|representative| symmetries(if swapped { !representative } else { *representative }).contains(&neighborhood))?;
+	hensel_letters(count).chars().nth(index)
+}
+
+
+// Neighbors that count for a rule on the cells next to each one
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Neighborhood {
+	Moore, // The 8 surrounding cells
+	VonNeumann, // The 4 orthogonal ones
+	Hexagonal // Every one but the top right and bottom left corners, a hexagonal grid sheared into the square matrix
+}
+
+impl Neighborhood {
+	// Bits of NEIGHBORS that are part of the neighborhood
+	pub fn mask(&self) -> u8 {
+		match self {
+			Neighborhood::Moore => 0xff,
+			Neighborhood::VonNeumann => 0x55,
+			Neighborhood::Hexagonal => 0xdd
+		}
+	}
+
+	// Suffix of the rulestring, e.g. B2/S013V
+	fn suffix(&self) -> &'static str {
+		match self {
+			Neighborhood::Moore => "",
+			Neighborhood::VonNeumann => "V",
+			Neighborhood::Hexagonal => "H"
+		}
+	}
+}
+
+// Cells counted by a Larger than Life rule around each cell
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum RangeShape {
+	Moore, // A square
+	VonNeumann, // A diamond
+	Circular
+}
+
+// Larger than Life: the alive cells up to a radius away are counted instead of the 8 neighbors, and cells are born or survive
+// when the count is in an interval
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Range {
+	pub radius: u8,
+	pub shape: RangeShape,
+	pub middle: bool, // Whether the cell itself is counted
+	pub birth: (u32, u32), // Both ends inclusive
+	pub survival: (u32, u32)
+}
+
+impl Range {
+	// (row, col) offsets of the counted cells
+	pub fn offsets(&self) -> Vec<(i32, i32)> {
+		let radius = self.radius as i32;
+		let mut offsets = Vec::new();
+		for row in -radius..=radius {
+			for col in -radius..=radius {
+				let inside = match self.shape {
+					RangeShape::Moore => true,
+					RangeShape::VonNeumann => row.abs() + col.abs() <= radius,
+					RangeShape::Circular => row * row + col * col <= radius * radius + radius // Within half a cell of the radius
+				};
+				if inside && (self.middle || row != 0 || col != 0) {
+					offsets.push((row, col));
+				}
+			}
+		}
+		offsets
+	}
+
+	// R5,C0,M1,S34..58,B34..45,NM (the HROT form S34-58 is also accepted). Returns the range and the amount of states
+	fn parse(text: &str) -> Result<(Self, u8), String> {
+		let invalid = |reason: &str| format!("invalid rule \"{}\", {}", text, reason);
+		let interval = |value: &str| -> Result<(u32, u32), String> {
+			let (min, max) = value.split_once("..").or(value.split_once('-')).unwrap_or((value, value));
+			match (min.parse::<u32>(), max.parse::<u32>()) {
+				(Ok(min), Ok(max)) if min <= max => Ok((min, max)),
+				_ => Err(invalid(format!("\"{}\" is not an interval like 34..58", value).as_str()))
+			}
+		};
+
+		let (mut radius, mut birth, mut survival) = (None, None, None);
+		let mut range = Range { radius: 1, shape: RangeShape::Moore, middle: false, birth: (0, 0), survival: (0, 0) };
+		let mut states = 2;
+		for field in text.split(',') {
+			let value = field.get(1..).unwrap_or("");
+			match field.chars().next() {
+				Some('R') => radius = Some(value.parse::<u8>().ok().filter(|radius| (1..=MAX_RADIUS).contains(radius))
+					.ok_or(invalid(format!("the radius must be between 1 and {}", MAX_RADIUS).as_str()))?),
+				Some('C') => states = match value.parse::<u8>() {
+					Ok(0..=2) => 2,
+					Ok(states) => states,
+					Err(_) => return Err(invalid("the amount of states must be between 2 and 255"))
+				},
+				Some('M') => range.middle = match value {
+					"0" => false,
+					"1" => true,
+					_ => return Err(invalid("M must be 0 or 1"))
+				},
+				Some('S') => survival = Some(interval(value)?),
+				Some('B') => birth = Some(interval(value)?),
+				Some('N') => range.shape = match value {
+					"M" => RangeShape::Moore,
+					"N" => RangeShape::VonNeumann,
+					"C" => RangeShape::Circular,
+					_ => return Err(invalid("the neighborhood must be NM (Moore), NN (von Neumann) or NC (circular)"))
+				},
+				_ => return Err(invalid(format!("unknown field \"{}\"", field).as_str()))
+			}
+		}
+
+		range.radius = radius.ok_or(invalid("missing the radius (R)"))?;
+		range.birth = birth.ok_or(invalid("missing the birth interval (B)"))?;
+		range.survival = survival.ok_or(invalid("missing the survival interval (S)"))?;
+		let cells = range.offsets().len() as u32;
+		if range.birth.1 > cells || range.survival.1 > cells {
+			return Err(invalid(format!("the range only has {} cells", cells).as_str()));
+		}
+		Ok((range, states))
+	}
+}
+
+// Well known block rules, whose tables are written like MCell's Margolus rules
+const NAMED_BLOCK_RULES: [(&str, [u8; 16]); 3] = [
+	("BBM", [0, 8, 4, 3, 2, 5, 9, 7, 1, 6, 10, 11, 12, 13, 14, 15]), // Billiard ball machine
+	("Critters", [15, 14, 13, 3, 11, 5, 6, 1, 7, 9, 10, 2, 12, 4, 8, 0]),
+	("Tron", [15, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 0])
+];
+
+// Margolus neighborhood: the matrix is split into 2x2 blocks and each block is replaced at once by its entry in the table. The
+// blocks start at even rows and columns on even generations, and at odd ones on odd generations. Cells are the bits of a block:
+// 1 is the top left one, 2 the top right, 4 the bottom left and 8 the bottom right
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Block {
+	pub table: [u8; 16],
+	steps: [[u8; 16]; 2] // Tables applied on even and odd generations
+}
+
+impl Block {
+	// Empty blocks must stay empty, or the whole matrix would change every generation. Tables that swap the empty and the full
+	// block (like Critters) are run on the complement of the matrix every other generation instead, where empty blocks stay empty
+	pub fn new(table: [u8; 16]) -> Result<Self, String> {
+		if let Some(block) = table.iter().find(|block| **block > 15) {
+			return Err(format!("invalid block {}, blocks go from 0 to 15", block));
+		}
+		let steps = match (table[0], table[15]) {
+			(0, _) => [table, table],
+			(15, 0) => {
+				let mut steps = [[0; 16]; 2];
+				for block in 0..16 {
+					steps[0][block] = 15 - table[block];
+					steps[1][block] = table[15 - block];
+				}
+				steps
+			},
+			_ => return Err("the empty block must stay empty, or swap with the full one".to_string())
+		};
+		Ok(Block { table, steps })
+	}
+
+	// MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15, the new block for each block from 0 to 15
+	fn parse(text: &str) -> Result<Self, String> {
+		let invalid = |reason: &str| format!("invalid rule \"{}\", {}", text, reason);
+		let entries = text.strip_prefix("MS,D").ok_or(invalid("expected MS,D followed by 16 blocks"))?;
+		let blocks = entries.split(';').map(|block| block.parse::<u8>().map_err(|_| invalid(format!("\"{}\" is not a block", block).as_str()))).collect::<Result<Vec<u8>, String>>()?;
+		let table: [u8; 16] = blocks.try_into().map_err(|blocks: Vec<u8>| invalid(format!("there are {} blocks instead of 16", blocks.len()).as_str()))?;
+		Block::new(table).map_err(|error| invalid(error.as_str()))
+	}
+
+	// Top left corner of the block the cell is part of in a generation, and the bit of the cell in it
+	pub fn locate(generation: i32, row: usize, col: usize) -> (i64, i64, u8) {
+		let offset = generation.rem_euclid(2) as i64;
+		let (top, left) = ((row as i64 - offset).div_euclid(2) * 2 + offset, (col as i64 - offset).div_euclid(2) * 2 + offset);
+		(top, left, ((row as i64 - top) * 2 + (col as i64 - left)) as u8)
+	}
+
+	pub fn next(&self, generation: i32, block: u8) -> u8 {
+		self.steps[generation.rem_euclid(2) as usize][block as usize]
+	}
+}
+
+// Life-like rule: whether a cell is born or survives depending on its amount of alive neighbors. Generations rules have more
+// than 2 states: instead of dying, alive cells go through the dying states 2, 3... and only the alive ones count as neighbors.
+// Rules with several species (Immigration, QuadLife) have an alive state for each one, and births take the species of most of
+// the neighbors. Rule tables decide the next state from the state of every neighbor instead
+#[derive(PartialEq, Clone, Debug)]
+pub struct Rule {
+	pub birth: [bool; 9], // For isotropic non-totalistic rules, whether any neighborhood with that amount of neighbors is included
+	pub survival: [bool; 9],
+	pub states: u8,
+	pub species: u8, // Alive states, 1 to species. Only Immigration and QuadLife have more than one
+	pub neighborhood: Neighborhood,
+	pub isotropic: Option<[[bool; 256]; 2]>, // Birth and survival of each neighborhood for non-totalistic rules, where the arrangement matters
+	pub range: Option<Range>, // Larger than Life rules, which ignore the counts above
+	pub table: Option<Arc<RuleTable>>, // Rule tables, which ignore everything above but the states and the neighborhood
+	pub block: Option<Block> // Block rules, which ignore everything above
+}
+
+impl Rule {
+	// B3/S23
+	pub fn life() -> Self {
+		let mut rule = Rule {
+			birth: [false; 9],
+			survival: [false; 9],
+			states: 2,
+			species: 1,
+			neighborhood: Neighborhood::Moore,
+			isotropic: None,
+			range: None,
+			table: None,
+			block: None
+		};
+		rule.birth[3] = true;
+		rule.survival[2] = true;
+		rule.survival[3] = true;
+		rule
+	}
+
+	// B3/S23 with 2 or 4 species
+	fn colored_life(species: u8) -> Self {
+		Rule {
+			states: species + 1,
+			species,
+			..Rule::life()
+		}
+	}
+
+	pub fn is_life(&self) -> bool {
+		*self == Rule::life()
+	}
+
+	// Accepts the B/S notation (B3/S23, b3s23) and the older S/B one (23/3), followed for Generations rules by the amount of
+	// states (B2/S/C3, /2/3), and by V or H for the von Neumann and hexagonal neighborhoods (B2/S013V). Each count can be limited
+	// to some neighborhoods in Hensel notation (B2-a/S12, B3/S2-i34q). Larger than Life rules have their own notation
+	// (R5,C0,M1,S34..58,B34..45,NM), and the multi-species ones are named (Immigration, QuadLife)
+	pub fn parse(text: &str) -> Result<Self, String> {
+		let text: String = text.chars().filter(|character| !character.is_whitespace()).collect::<String>().to_uppercase();
+		match text.as_str() {
+			"IMMIGRATION" => return Ok(Rule::colored_life(2)),
+			"QUADLIFE" => return Ok(Rule::colored_life(4)),
+			_ => {}
+		}
+		if let Some((_, table)) = NAMED_BLOCK_RULES.iter().find(|(name, _)| name.to_uppercase() == text) {
+			return Ok(Rule::from_block(Block::new(*table)?));
+		}
+		if text.starts_with("MS,") {
+			return Ok(Rule::from_block(Block::parse(text.as_str())?));
+		}
+		if text.starts_with('R') && text.contains(',') {
+			let (range, states) = Range::parse(text.as_str())?;
+			return Ok(Rule {
+				birth: [false; 9],
+				survival: [false; 9],
+				states,
+				species: 1,
+				neighborhood: Neighborhood::Moore,
+				isotropic: None,
+				range: Some(range),
+				table: None,
+				block: None
+			});
+		}
+		let (text_without_suffix, neighborhood) = match text.strip_suffix('V').or(text.strip_suffix('H')) {
+			Some(rest) if text.ends_with('V') => (rest, Neighborhood::VonNeumann),
+			Some(rest) => (rest, Neighborhood::Hexagonal),
+			None => (text.as_str(), Neighborhood::Moore)
+		};
+		let (counts_text, states) = match text_without_suffix.rsplit_once('/') {
+			Some((counts, states)) if counts.contains('/') => (counts, Rule::parse_states(states, text.as_str())?),
+			_ => (text_without_suffix, 2)
+		};
+		let counts = match counts_text.strip_prefix('B') {
+			Some(counts) => counts.split_once('S').map(|(birth, survival)| (birth.trim_end_matches('/'), survival)),
+			None => counts_text.split_once('/').map(|(survival, birth)| (birth, survival))
+		};
+		let (birth, survival) = counts.ok_or(format!("invalid rule \"{}\", expected the B3/S23 notation", text))?;
+		let (birth, birth_neighborhoods) = Rule::parse_conditions(birth, text.as_str())?;
+		let (survival, survival_neighborhoods) = Rule::parse_conditions(survival, text.as_str())?;
+		let neighbors = neighborhood.mask().count_ones() as usize;
+		if birth[neighbors + 1..].contains(&true) || survival[neighbors + 1..].contains(&true) {
+			return Err(format!("invalid rule \"{}\", the neighborhood only has {} neighbors", text, neighbors));
+		}
+
+		// Rules whose counts are all whole are totalistic, however they were written
+		let whole = |counts: &[bool; 9], neighborhoods: &[bool; 256]| (0..=255u8).all(|neighborhood| neighborhoods[neighborhood as usize] == counts[neighborhood.count_ones() as usize]);
+		let totalistic = whole(&birth, &birth_neighborhoods) && whole(&survival, &survival_neighborhoods);
+		if !totalistic && neighborhood != Neighborhood::Moore {
+			return Err(format!("invalid rule \"{}\", Hensel notation is only supported on the 8 neighbors", text));
+		}
+		Ok(Rule {
+			birth,
+			survival,
+			states,
+			species: 1,
+			neighborhood,
+			isotropic: (!totalistic).then_some([birth_neighborhoods, survival_neighborhoods]),
+			range: None,
+			table: None,
+			block: None
+		})
+	}
+
+	pub fn from_table(table: Arc<RuleTable>) -> Self {
+		Rule {
+			birth: [false; 9],
+			survival: [false; 9],
+			states: table.states,
+			species: 1,
+			neighborhood: table.neighborhood,
+			isotropic: None,
+			range: None,
+			table: Some(table),
+			block: None
+		}
+	}
+
+	pub fn from_block(block: Block) -> Self {
+		Rule {
+			birth: [false; 9],
+			survival: [false; 9],
+			states: 2,
+			species: 1,
+			neighborhood: Neighborhood::Moore,
+			isotropic: None,
+			range: None,
+			table: None,
+			block: Some(block)
+		}
+	}
+
+	fn parse_states(states: &str, text: &str) -> Result<u8, String> {
+		match states.trim_start_matches(['C', 'G']).parse::<u8>() {
+			Ok(states) if states >= 2 => Ok(states),
+			_ => Err(format!("invalid rule \"{}\", the amount of states must be between 2 and 255", text))
+		}
+	}
+
+	// Counts, each followed by the letters of the only neighborhoods it includes, or by a minus and those it excludes
+	fn parse_conditions(conditions: &str, text: &str) -> Result<([bool; 9], [bool; 256]), String> {
+		let mut counts = [false; 9];
+		let mut neighborhoods = [false; 256];
+		let mut characters = conditions.chars().peekable();
+		while let Some(character) = characters.next() {
+			let count = match character.to_digit(10) {
+				Some(count) if count <= 8 => count as usize,
+				_ => return Err(format!("invalid rule \"{}\", only Life-like rules with neighbor counts from 0 to 8 are supported", text))
+			};
+			let excluded = characters.next_if_eq(&'-').is_some();
+			let mut letters = String::new();
+			while let Some(letter) = characters.next_if(|character| character.is_ascii_alphabetic()) {
+				let letter = letter.to_ascii_lowercase();
+				if !hensel_letters(count).contains(letter) {
+					return Err(format!("invalid rule \"{}\", {}{} is not a neighborhood in Hensel notation", text, count, letter));
+				}
+				letters.push(letter);
+			}
+
+			for neighborhood in (0..=255u8).filter(|neighborhood| neighborhood.count_ones() as usize == count) {
+				let listed = hensel_letter(neighborhood).is_some_and(|letter| letters.contains(letter));
+				if letters.is_empty() || listed != excluded {
+					neighborhoods[neighborhood as usize] = true;
+					counts[count] = true;
+				}
+			}
+		}
+		Ok((counts, neighborhoods))
+	}
+
+	// Whether the neighborhood (a bitmask, see NEIGHBORS) gives birth to a dead cell or keeps an alive one alive
+	fn allows(&self, alive: bool, neighborhood: u8) -> bool {
+		match (self.isotropic.as_ref(), alive) {
+			(Some(neighborhoods), _) => neighborhoods[alive as usize][neighborhood as usize],
+			(None, false) => self.birth[(neighborhood & self.neighborhood.mask()).count_ones() as usize],
+			(None, true) => self.survival[(neighborhood & self.neighborhood.mask()).count_ones() as usize]
+		}
+	}
+
+	// Whether a cell in that state counts as a neighbor and as part of the population: only alive cells in Generations rules, any
+	// species in multi-species ones, and every state but the empty one in rule tables
+	pub fn alive(&self, state: u8) -> bool {
+		state != 0 && (state <= self.species || self.table.is_some())
+	}
+
+	// Distance up to which cells affect each other in one generation
+	pub fn radius(&self) -> u32 {
+		self.range.map_or(1, |range| range.radius as u32)
+	}
+
+	// Alive cells that don't survive start dying, dying cells keep going until they are dead
+	fn transition(&self, state: u8, born: bool, survives: bool) -> u8 {
+		match state {
+			0 => born as u8,
+			1 if survives => 1,
+			_ if state + 1 >= self.states => 0,
+			_ => state + 1
+		}
+	}
+
+	// Next state of a cell given its alive neighbors as a bitmask
+	pub fn next(&self, state: u8, neighborhood: u8) -> u8 {
+		self.transition(state, state == 0 && self.allows(false, neighborhood), state == 1 && self.allows(true, neighborhood))
+	}
+
+	// Next state of a cell in a multi-species rule given the states of its neighbors (see NEIGHBORS). Births take the species most
+	// of the alive neighbors have, or in QuadLife the missing one when 3 neighbors are all different
+	pub fn next_species(&self, state: u8, neighbors: &[u8; 8]) -> u8 {
+		let mut neighborhood = 0;
+		let mut counts = [0; 256];
+		for (bit, neighbor) in neighbors.iter().enumerate() {
+			if self.alive(*neighbor) && self.neighborhood.mask() & (1 << bit) != 0 {
+				neighborhood |= 1 << bit;
+				counts[*neighbor as usize] += 1;
+			}
+		}
+		if state != 0 {
+			return if self.allows(true, neighborhood) { state } else { 0 };
+		}
+		if !self.allows(false, neighborhood) {
+			return 0;
+		}
+
+		let most = (1..=self.species).max_by_key(|species| (counts[*species as usize], std::cmp::Reverse(*species))).unwrap_or(1);
+		let present = (1..=self.species).filter(|species| counts[*species as usize] > 0).count();
+		match (counts[most as usize], (1..=self.species).find(|species| counts[*species as usize] == 0)) {
+			(1, Some(missing)) if present == self.species as usize - 1 && present > 1 => missing,
+			_ => most
+		}
+	}
+
+	// Next state of a cell in a Larger than Life rule, given the amount of alive cells in its range
+	pub fn next_in_range(&self, state: u8, alive_cells: u32) -> u8 {
+		let range = match self.range {
+			Some(range) => range,
+			None => return state
+		};
+		let within = |(min, max): (u32, u32)| (min..=max).contains(&alive_cells);
+		self.transition(state, within(range.birth), within(range.survival))
+	}
+
+	// Only for totalistic rules, where the amount of neighbors is enough
+	pub fn next_state(&self, alive: bool, neighbors: i8) -> bool {
+		if alive {
+			self.survival[neighbors as usize]
+		} else {
+			self.birth[neighbors as usize]
+		}
+	}
+}
+
+impl Default for Rule {
+	fn default() -> Self {
+		Rule::life()
+	}
+}
+
+impl fmt::Display for Rule {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if let Some(table) = self.table.as_ref() {
+			return write!(f, "{}", table.name);
+		}
+		if let Some(block) = self.block {
+			return match NAMED_BLOCK_RULES.iter().find(|(_, table)| *table == block.table) {
+				Some((name, _)) => write!(f, "{}", name),
+				None => write!(f, "MS,D{}", block.table.map(|block| block.to_string()).join(";"))
+			};
+		}
+		match self.species {
+			2 => return write!(f, "Immigration"),
+			4 => return write!(f, "QuadLife"),
+			_ => {}
+		}
+		if let Some(range) = self.range {
+			let shape = match range.shape {
+				RangeShape::Moore => "M",
+				RangeShape::VonNeumann => "N",
+				RangeShape::Circular => "C"
+			};
+			return write!(f, "R{},C{},M{},S{}..{},B{}..{},N{}", range.radius, if self.states > 2 { self.states } else { 0 }, range.middle as u8,
+				range.survival.0, range.survival.1, range.birth.0, range.birth.1, shape);
+		}
+		let conditions = |counts: &[bool; 9], index: usize| -> String {
+			let mut text = String::new();
+			for count in (0..9).filter(|count| counts[*count]) {
+				text += count.to_string().as_str();
+				let neighborhoods = match self.isotropic.as_ref() {
+					Some(neighborhoods) => &neighborhoods[index],
+					None => continue
+				};
+				// Each count lists its included neighborhoods, or the excluded ones if they are fewer
+				let (mut included, mut excluded) = (String::new(), String::new());
+				for letter in hensel_letters(count).chars() {
+					let neighborhood = (0..=255u8).find(|neighborhood| neighborhood.count_ones() as usize == count && hensel_letter(*neighborhood) == Some(letter));
+					match neighborhood.is_some_and(|neighborhood| neighborhoods[neighborhood as usize]) {
+						true => included.push(letter),
+						false => excluded.push(letter)
+					}
+				}
+				if !excluded.is_empty() {
+					text += if excluded.len() < included.len() { format!("-{}", excluded) } else { included }.as_str();
+				}
+			}
+			text
+		};
+		write!(f, "B{}/S{}", conditions(&self.birth, 0), conditions(&self.survival, 1))?;
+		if self.states > 2 {
+			write!(f, "/C{}", self.states)?;
+		}
+		write!(f, "{}", self.neighborhood.suffix())
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_and_display_generations() {
+		for text in ["B3/S23", "B36/S23", "B2/S/C3"] {
+			assert_eq!(Rule::parse(text).unwrap().to_string(), text);
+		}
+		assert!(Rule::parse("23/3").unwrap().is_life());
+		assert!(Rule::parse("b3s23").unwrap().is_life());
+		assert_eq!(Rule::parse("/2/3").unwrap().to_string(), "B2/S/C3");
+		assert_eq!(Rule::parse("B2/S/C3").unwrap().states, 3);
+		for text in ["B9/S", "B2/S/C1"] {
+			assert!(Rule::parse(text).is_err(), "{} should be rejected", text);
+		}
+	}
+
+	#[test]
+	fn hensel_notation() {
+		assert_eq!(hensel_letter(0x03), Some('a'));
+		assert_eq!(hensel_letter(0x0c), Some('a')); // Rotated
+		assert_eq!(hensel_letter(0x0a), Some('c'));
+		assert_eq!(hensel_letter(0x05), Some('e'));
+		assert_eq!(hensel_letter(0xfc), Some('a')); // 6 neighbors, the dead ones are a 2a
+		assert_eq!(hensel_letter(0x00), None);
+
+		let rule = Rule::parse("B2-a/S12").unwrap();
+		let [birth, survival] = rule.isotropic.unwrap();
+		assert!(!birth[0x03] && birth[0x05] && birth[0x11]);
+		assert!(survival[0x01] && survival[0x03]);
+	}
+
+	#[test]
+	fn parse_and_display_hensel() {
+		for text in ["B2-a/S12", "B3/S2-i34q", "B2a/S"] {
+			assert_eq!(Rule::parse(text).unwrap().to_string(), text);
+		}
+		assert!(Rule::parse("B2ce/S23").is_ok_and(|rule| rule.isotropic.is_some()));
+		assert!(Rule::parse("B2cekain/S23").is_ok_and(|rule| rule.isotropic.is_none())); // Every letter is the whole count
+		assert!(Rule::parse("B2x/S").is_err());
+	}
+
+	#[test]
+	fn range_parse() {
+		let (range, states) = Range::parse("R2,C3,M1,S1..3,B2-4,NN").unwrap();
+		assert_eq!(states, 3);
+		assert_eq!((range.radius, range.shape, range.middle, range.birth, range.survival), (2, RangeShape::VonNeumann, true, (2, 4), (1, 3)));
+		assert_eq!(range.offsets().len(), 13);
+		assert_eq!(Range::parse("R1,C0,M0,S2..3,B3..3,NM").unwrap().0.offsets().len(), 8);
+		for text in ["R1,C0,M0,S3..2,B3..3,NM", "R1,C0,M0,B3..3,NM", "R1,C0,M0,S2..9,B3..3,NM", "R11,C0,M1,S1..2,B1..2,NM"] {
+			assert!(Range::parse(text).is_err(), "{} should be rejected", text);
+		}
+	}
+
+	#[test]
+	fn parse_and_display_larger_than_life() {
+		for text in ["R5,C0,M1,S34..58,B34..45,NM", "R2,C3,M0,S1..3,B2..2,NC"] {
+			assert_eq!(Rule::parse(text).unwrap().to_string(), text);
+		}
+		assert_eq!(Rule::parse("R5,C0,M1,S34-58,B34-45,NM").unwrap().to_string(), "R5,C0,M1,S34..58,B34..45,NM");
+	}
+
+	#[test]
+	fn parse_and_display_neighborhoods() {
+		for text in ["B2/S34H", "B1/S1V", "B2/S/C3V"] {
+			assert_eq!(Rule::parse(text).unwrap().to_string(), text);
+		}
+		assert_eq!(Rule::parse("B2/S34H").unwrap().neighborhood, Neighborhood::Hexagonal);
+		for text in ["B5/S4V", "B7/SH", "B2a/S1H"] {
+			assert!(Rule::parse(text).is_err(), "{} should be rejected", text);
+		}
+	}
+
+	#[test]
+	fn parse_and_display_species() {
+		let immigration = Rule::parse("Immigration").unwrap();
+		assert_eq!((immigration.species, immigration.states), (2, 3));
+		assert_eq!(immigration.to_string(), "Immigration");
+		let quadlife = Rule::parse("QuadLife").unwrap();
+		assert_eq!((quadlife.species, quadlife.states), (4, 5));
+		assert_eq!(quadlife.to_string(), "QuadLife");
+	}
+
+	#[test]
+	fn births_take_the_species_of_most_neighbors() {
+		let immigration = Rule::parse("Immigration").unwrap();
+		assert_eq!(immigration.next_species(0, &[2, 1, 2, 0, 0, 0, 0, 0]), 2);
+		assert_eq!(immigration.next_species(0, &[1, 1, 2, 0, 0, 0, 0, 0]), 1);
+		assert_eq!(immigration.next_species(2, &[1, 1, 0, 0, 0, 0, 0, 0]), 2);
+		assert_eq!(immigration.next_species(1, &[1, 0, 0, 0, 0, 0, 0, 0]), 0);
+		// Three different species give birth to the missing one
+		let quadlife = Rule::parse("QuadLife").unwrap();
+		assert_eq!(quadlife.next_species(0, &[1, 2, 4, 0, 0, 0, 0, 0]), 3);
+		assert_eq!(quadlife.next_species(0, &[1, 1, 4, 0, 0, 0, 0, 0]), 1);
+	}
+
+	#[test]
+	fn parse_and_display_block_rules() {
+		for text in ["BBM", "Critters", "Tron", "MS,D0;1;2;3;4;5;6;7;8;9;10;11;12;13;14;15"] {
+			assert_eq!(Rule::parse(text).unwrap().to_string(), text);
+		}
+		// Tables with a name are shown by it
+		assert_eq!(Rule::parse("MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15").unwrap().to_string(), "BBM");
+		for text in ["MS,D0;1;2", "MS,D1;0;2;3;4;5;6;7;8;9;10;11;12;13;14;15", "MS,D0;1;2;3;4;5;6;7;8;9;10;11;12;13;14;16"] {
+			assert!(Rule::parse(text).is_err(), "{} should be rejected", text);
+		}
+		assert!(Block::new([16; 16]).is_err());
+	}
+
+	#[test]
+	fn block_tables() {
+		let bbm = Block::new(NAMED_BLOCK_RULES[0].1).unwrap();
+		assert_eq!((bbm.next(0, 1), bbm.next(1, 1), bbm.next(0, 0)), (8, 8, 0));
+		// Critters swaps the empty and the full block, so odd generations run on the complement
+		let critters = Block::new(NAMED_BLOCK_RULES[1].1).unwrap();
+		assert_eq!((critters.next(0, 0), critters.next(1, 0)), (0, 0));
+		assert_eq!((critters.next(0, 1), critters.next(1, 1)), (15 - critters.table[1], critters.table[14]));
+
+		assert_eq!(Block::locate(0, 3, 4), (2, 4, 2));
+		assert_eq!(Block::locate(1, 3, 4), (3, 3, 1));
+	}
+}
@@ -0,0 +1,344 @@
+extern crate sdl2;
+
+
+use std::time::Duration;
+use std::time::Instant;
+
+use rand::Rng;
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::Color;
+use sdl2::rect::Point;
+use sdl2::rect::Rect;
+use sdl2::render::TextureQuery;
+use sdl2::ttf::Font;
+
+use crate::button::Button;
+use crate::engine::Engine;
+use crate::rule::Rule;
+use crate::rule_table;
+
+
+const PANEL_WIDTH: u32 = 640;
+const PANEL_HEIGHT: u32 = 370;
+const PADDING: i32 = 10;
+const FIELD_WIDTH: u32 = 400;
+const FIELD_HEIGHT: u32 = 28;
+const CHECKBOX_SIZE: u32 = 20;
+const CHECKBOX_SPACING: i32 = 30;
+const DROPDOWN_WIDTH: u32 = 210;
+const DROPDOWN_ROW_HEIGHT: u32 = 20;
+const BTN_WIDTH: u32 = 70;
+const BTN_HEIGHT: u32 = 30;
+const BTN_REGION_WIDTH: u32 = 170;
+
+const PREVIEW_SIZE: u32 = 64; // Columns and rows of the preview, a multiple of SUB_MATRIX_CHUNK_SIZE
+const PREVIEW_CELL_SIZE: u32 = 3;
+const PREVIEW_SOUP_SIZE: u32 = 32; // Width and height of the random soup in the middle of the preview
+const PREVIEW_COOLDOWN: Duration = Duration::from_millis(60);
+
+// Well known rules, by name
+const NAMED_RULES: [(&str, &str); 22] = [
+	("Life", "B3/S23"),
+	("HighLife", "B36/S23"),
+	("Day & Night", "B3678/S34678"),
+	("Seeds", "B2/S"),
+	("Life without death", "B3/S012345678"),
+	("Replicator", "B1357/S1357"),
+	("2x2", "B36/S125"),
+	("Maze", "B3/S12345"),
+	("Diamoeba", "B35678/S5678"),
+	("Morley", "B368/S245"),
+	("Anneal", "B4678/S35678"),
+	("tlife", "B3/S2-i34q"),
+	("Hexagonal B2/S34", "B2/S34H"),
+	("Brian's Brain", "B2/S/C3"),
+	("Star Wars", "B2/S345/C4"),
+	("Bosco's rule", "R5,C0,M1,S34..58,B34..45,NM"),
+	("Immigration", "Immigration"),
+	("QuadLife", "QuadLife"),
+	("WireWorld", "WireWorld"),
+	("Billiard ball machine", "BBM"),
+	("Critters", "Critters"),
+	("Tron", "Tron")
+];
+
+
+pub enum RuleEditorAction {
+	Apply(Box<Rule>),
+	ApplyToSelection(Box<Rule>), // Only to the chunks the selection touches, as a region of the universe
+	Close
+}
+
+// Panel over the grid to write a rule (or toggle its counts, or pick a named one) and try it on a random soup before applying it
+pub struct RuleEditor {
+	rect: Rect,
+	text: String,
+	candidate: Result<Rule, String>,
+	named: Option<usize>, // Named rule the candidate is
+	dropdown_open: bool,
+	opening: bool, // The key that opened the editor also types its letter, which is ignored until the key is released
+	preview: Engine,
+	last_preview_step: Instant,
+	btn_apply: Button,
+	btn_cancel: Button,
+	btn_apply_region: Button
+}
+
+impl RuleEditor {
+	pub fn new(rule: &Rule, has_selection: bool) -> Self {
+		let rect = Rect::new(crate::H_MARGIN as i32 + (crate::GRID_WIDTH - PANEL_WIDTH) as i32 / 2, crate::V_MARGIN as i32 + 60, PANEL_WIDTH, PANEL_HEIGHT);
+		let buttons_y = rect.bottom() - PADDING - BTN_HEIGHT as i32;
+		let mut editor = Self {
+			rect,
+			text: rule.to_string(),
+			candidate: Ok(rule.clone()),
+			named: None,
+			dropdown_open: false,
+			opening: true,
+			preview: Engine::with_size(PREVIEW_SIZE),
+			last_preview_step: Instant::now(),
+			btn_apply: Button::new(crate::COLOR_GREEN, Rect::new(rect.x + PADDING, buttons_y, BTN_WIDTH, BTN_HEIGHT), "Apply".to_string()),
+			btn_cancel: Button::new(crate::COLOR_RED, Rect::new(rect.x + PADDING * 2 + BTN_WIDTH as i32, buttons_y, BTN_WIDTH, BTN_HEIGHT), "Cancel".to_string()),
+			btn_apply_region: Button::new(crate::COLOR_BLUE, Rect::new(rect.x + PADDING * 3 + BTN_WIDTH as i32 * 2, buttons_y, BTN_REGION_WIDTH, BTN_HEIGHT), "Apply to selection".to_string())
+		};
+		editor.btn_apply_region.set_hidden(!has_selection);
+		editor.update_candidate();
+		editor
+	}
+
+	fn field_rect(&self) -> Rect {
+		Rect::new(self.rect.x + PADDING, self.rect.y + 40, FIELD_WIDTH, FIELD_HEIGHT)
+	}
+
+	fn dropdown_rect(&self) -> Rect {
+		Rect::new(self.rect.right() - PADDING - DROPDOWN_WIDTH as i32, self.rect.y + 40, DROPDOWN_WIDTH, FIELD_HEIGHT)
+	}
+
+	fn dropdown_row_rect(&self, index: usize) -> Rect {
+		let dropdown = self.dropdown_rect();
+		Rect::new(dropdown.x, dropdown.bottom() + (index as u32 * DROPDOWN_ROW_HEIGHT) as i32, DROPDOWN_WIDTH, DROPDOWN_ROW_HEIGHT)
+	}
+
+	// Birth checkboxes on the first row and survival ones on the second
+	fn checkbox_rect(&self, survival: bool, count: usize) -> Rect {
+		Rect::new(self.rect.x + PADDING + 80 + count as i32 * CHECKBOX_SPACING, self.rect.y + 130 + survival as i32 * CHECKBOX_SPACING, CHECKBOX_SIZE, CHECKBOX_SIZE)
+	}
+
+	fn preview_rect(&self) -> Rect {
+		let side = PREVIEW_SIZE * PREVIEW_CELL_SIZE;
+		Rect::new(self.rect.right() - PADDING - side as i32, self.rect.y + 110, side, side)
+	}
+
+	// Only the counts of Life-like and Generations rules can be toggled, the others have their own notation
+	fn editable(&self) -> Option<&Rule> {
+		self.candidate.as_ref().ok().filter(|rule| rule.range.is_none() && rule.table.is_none() && rule.block.is_none() && rule.species == 1 && rule.isotropic.is_none())
+	}
+
+	fn update_candidate(&mut self) {
+		let previous = self.candidate.as_ref().ok().cloned();
+		self.candidate = match self.text.trim() {
+			"" => Err("type a rule, e.g. B3/S23".to_string()),
+			text => rule_table::parse_rule(text)
+		};
+		// Rule tables are named after themselves, no need to load them again
+		let name = self.candidate.as_ref().map(|rule| rule.to_string()).ok();
+		self.named = NAMED_RULES.iter().position(|(_, text)| Rule::parse(text).map_or(text.to_string(), |rule| rule.to_string()) == name.as_deref().unwrap_or_default());
+
+		// A new soup for every new rule, an invalid one keeps the last preview running
+		if let Ok(rule) = self.candidate.as_ref() {
+			if previous.as_ref() != Some(rule) || self.preview.population() == 0 {
+				self.new_soup();
+			}
+		}
+	}
+
+	fn new_soup(&mut self) {
+		let rule = match self.candidate.as_ref() {
+			Ok(rule) => rule.clone(),
+			Err(_) => self.preview.rule().clone()
+		};
+		// Alive cells, of any species or any state of a rule table
+		let highest = if rule.table.is_some() { rule.states - 1 } else { rule.species };
+		let mut rng = rand::thread_rng();
+		self.preview = Engine::with_size(PREVIEW_SIZE);
+		self.preview.set_rule(rule);
+		let start = (PREVIEW_SIZE - PREVIEW_SOUP_SIZE) / 2;
+		for row in start..start + PREVIEW_SOUP_SIZE {
+			for col in start..start + PREVIEW_SOUP_SIZE {
+				if rng.gen_bool(0.5) {
+					self.preview.set_state(row as usize, col as usize, rng.gen_range(1..=highest));
+				}
+			}
+		}
+	}
+
+	pub fn handle_event(&mut self, event: &Event) -> Option<RuleEditorAction> {
+		match event {
+			Event::KeyUp { .. } => self.opening = false,
+			Event::TextInput { text, .. } if !self.opening => {
+				self.text += text.as_str();
+				self.update_candidate();
+			},
+			Event::KeyDown { keycode: Some(Keycode::Backspace), .. } => {
+				self.text.pop();
+				self.update_candidate();
+			},
+			Event::KeyDown { keycode: Some(Keycode::Return | Keycode::KpEnter), .. } => return self.apply(),
+			Event::KeyDown { keycode: Some(Keycode::Escape), .. } if self.dropdown_open => self.dropdown_open = false,
+			Event::KeyDown { keycode: Some(Keycode::Escape), .. } => return Some(RuleEditorAction::Close),
+			Event::MouseMotion { x, y, .. } => {
+				self.btn_apply.update_hover(*x, *y);
+				self.btn_cancel.update_hover(*x, *y);
+				self.btn_apply_region.update_hover(*x, *y);
+			},
+			Event::MouseButtonDown { mouse_btn: MouseButton::Left, x, y, .. } => return self.click(Point::new(*x, *y)),
+			_ => {}
+		}
+		None
+	}
+
+	fn click(&mut self, point: Point) -> Option<RuleEditorAction> {
+		// The open list is on top of everything else, clicking anywhere closes it
+		if self.dropdown_open {
+			self.dropdown_open = false;
+			if let Some(index) = (0..NAMED_RULES.len()).find(|index| self.dropdown_row_rect(*index).contains_point(point)) {
+				self.text = NAMED_RULES[index].1.to_string();
+				self.update_candidate();
+			}
+			return None;
+		}
+
+		if self.dropdown_rect().contains_point(point) {
+			self.dropdown_open = true;
+		} else if self.preview_rect().contains_point(point) {
+			self.new_soup();
+		} else if self.btn_apply.is_hovered() {
+			return self.apply();
+		} else if self.btn_apply_region.is_hovered() {
+			return self.candidate.as_ref().ok().map(|rule| RuleEditorAction::ApplyToSelection(Box::new(rule.clone())));
+		} else if self.btn_cancel.is_hovered() {
+			return Some(RuleEditorAction::Close);
+		} else if let Some(rule) = self.editable() {
+			let neighbors = rule.neighborhood.mask().count_ones() as usize;
+			for (survival, count) in [false, true].into_iter().flat_map(|survival| (0..=neighbors).map(move |count| (survival, count))) {
+				if self.checkbox_rect(survival, count).contains_point(point) {
+					let mut rule = rule.clone();
+					let counts = if survival { &mut rule.survival } else { &mut rule.birth };
+					counts[count] = !counts[count];
+					self.text = rule.to_string();
+					self.update_candidate();
+					break;
+				}
+			}
+		}
+		None
+	}
+
+	fn apply(&self) -> Option<RuleEditorAction> {
+		self.candidate.as_ref().ok().map(|rule| RuleEditorAction::Apply(Box::new(rule.clone())))
+	}
+
+	pub fn draw(&mut self, canvas: &mut sdl2::render::Canvas<sdl2::video::Window>, font: &Font) {
+		if self.last_preview_step.elapsed() > PREVIEW_COOLDOWN {
+			self.preview.iterate();
+			self.last_preview_step = Instant::now();
+		}
+
+		canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+		canvas.set_draw_color(Color::RGBA(0, 0, 0, 230));
+		let _ = canvas.fill_rect(self.rect);
+		canvas.set_blend_mode(sdl2::render::BlendMode::None);
+		canvas.set_draw_color(crate::COLOR_BLACK_3);
+		let _ = canvas.draw_rect(self.rect);
+
+		draw_text(canvas, font, "Rule editor (Enter applies, Esc cancels)", self.rect.x + PADDING, self.rect.y + PADDING, crate::COLOR_WHITE);
+
+		// Rulestring field, and whether it's valid
+		let field = self.field_rect();
+		canvas.set_draw_color(crate::COLOR_BLACK_1);
+		let _ = canvas.fill_rect(field);
+		canvas.set_draw_color(crate::COLOR_BLUE);
+		let _ = canvas.draw_rect(field);
+		draw_text(canvas, font, format!("{}_", self.text).as_str(), field.x + 5, field.y + 5, crate::COLOR_WHITE);
+		let validation_y = field.bottom() + 6;
+		match self.candidate.as_ref() {
+			Ok(rule) => draw_text(canvas, font, format!("{} ({} states)", rule, rule.states).as_str(), field.x, validation_y, crate::COLOR_GREEN),
+			Err(error) => draw_text(canvas, font, error.as_str(), field.x, validation_y, crate::COLOR_RED)
+		};
+
+		// Counts of the neighbors, greyed out when they can't be toggled
+		let editable = self.editable().cloned();
+		let neighbors = editable.as_ref().map_or(8, |rule| rule.neighborhood.mask().count_ones() as usize);
+		for count in 0..=8 {
+			let rect = self.checkbox_rect(false, count);
+			draw_text(canvas, font, count.to_string().as_str(), rect.x + 5, rect.y - 22, crate::COLOR_WHITE);
+		}
+		for survival in [false, true] {
+			let label_rect = self.checkbox_rect(survival, 0);
+			draw_text(canvas, font, if survival { "Survival" } else { "Birth" }, self.rect.x + PADDING, label_rect.y, crate::COLOR_WHITE);
+			for count in 0..=8 {
+				let rect = self.checkbox_rect(survival, count);
+				let checked = editable.as_ref().is_some_and(|rule| if survival { rule.survival[count] } else { rule.birth[count] });
+				let enabled = editable.is_some() && count <= neighbors;
+				canvas.set_draw_color(if enabled { crate::COLOR_WHITE } else { crate::COLOR_BLACK_3 });
+				let _ = canvas.draw_rect(rect);
+				if checked {
+					canvas.set_draw_color(crate::COLOR_GREEN);
+					let _ = canvas.fill_rect(Rect::new(rect.x + 4, rect.y + 4, CHECKBOX_SIZE - 8, CHECKBOX_SIZE - 8));
+				}
+			}
+		}
+		if editable.is_none() {
+			draw_text(canvas, font, "Only Life-like rules can be toggled", self.rect.x + PADDING, self.checkbox_rect(true, 0).bottom() + 10, crate::COLOR_BLACK_3);
+		}
+
+		// Preview of the candidate rule on a random soup
+		let preview = self.preview_rect();
+		draw_text(canvas, font, "Preview (click for a new soup)", preview.x, preview.y - 22, crate::COLOR_WHITE);
+		canvas.set_draw_color(crate::COLOR_BLACK_1);
+		let _ = canvas.fill_rect(preview);
+		for row in 0..PREVIEW_SIZE {
+			for col in 0..PREVIEW_SIZE {
+				let state = self.preview.get_state(row as usize, col as usize);
+				if state != 0 {
+					canvas.set_draw_color(crate::state_color(state, self.preview.rule()));
+					let _ = canvas.fill_rect(Rect::new(preview.x + (col * PREVIEW_CELL_SIZE) as i32, preview.y + (row * PREVIEW_CELL_SIZE) as i32, PREVIEW_CELL_SIZE, PREVIEW_CELL_SIZE));
+				}
+			}
+		}
+		canvas.set_draw_color(crate::COLOR_BLACK_3);
+		let _ = canvas.draw_rect(preview);
+
+		self.btn_apply.set_hidden(self.candidate.is_err());
+		self.btn_apply.draw(canvas, font);
+		self.btn_cancel.draw(canvas, font);
+		if self.candidate.is_ok() {
+			self.btn_apply_region.draw(canvas, font);
+		}
+
+		// Named rules, the open list goes over the rest
+		let dropdown = self.dropdown_rect();
+		canvas.set_draw_color(crate::COLOR_BLACK_2);
+		let _ = canvas.fill_rect(dropdown);
+		let name = self.named.map_or("Named rules", |index| NAMED_RULES[index].0);
+		draw_text(canvas, font, format!("{} v", name).as_str(), dropdown.x + 5, dropdown.y + 5, crate::COLOR_WHITE);
+		if self.dropdown_open {
+			for (index, (name, _)) in NAMED_RULES.iter().enumerate() {
+				let rect = self.dropdown_row_rect(index);
+				canvas.set_draw_color(if Some(index) == self.named { crate::COLOR_BLACK_3 } else { crate::COLOR_BLACK_2 });
+				let _ = canvas.fill_rect(rect);
+				draw_text(canvas, font, name, rect.x + 5, rect.y + 1, crate::COLOR_WHITE);
+			}
+		}
+	}
+}
+
+fn draw_text(canvas: &mut sdl2::render::Canvas<sdl2::video::Window>, font: &Font, text: &str, x: i32, y: i32, color: Color) {
+	let surface = font.render(text).blended(color).unwrap();
+	let texture_creator = canvas.texture_creator();
+	let texture = texture_creator.create_texture_from_surface(&surface).unwrap();
+	let TextureQuery { width, height, .. } = texture.query();
+	let _ = canvas.copy(&texture, None, Some(Rect::new(x, y, width, height)));
+}
@@ -0,0 +1,510 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::rule::Neighborhood;
+use crate::rule::Rule;
+
+
+const RULES_FOLDER: &str = "./rules"; // Where rules named in RLE headers are looked for, besides the current folder
+
+// Set of states, one bit for each of the 256 possible ones
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+struct StateSet([u64; 4]);
+
+impl StateSet {
+	fn empty() -> Self {
+		StateSet([0; 4])
+	}
+
+	fn all() -> Self {
+		StateSet([u64::MAX; 4])
+	}
+
+	fn single(state: u8) -> Self {
+		let mut set = StateSet::empty();
+		set.insert(state);
+		set
+	}
+
+	fn insert(&mut self, state: u8) {
+		self.0[state as usize / 64] |= 1 << (state % 64);
+	}
+
+	fn contains(&self, state: u8) -> bool {
+		self.0[state as usize / 64] & (1 << (state % 64)) != 0
+	}
+
+	fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+		(0..=255u8).filter(|state| self.contains(*state))
+	}
+}
+
+// One line of the table, after its bound variables were given a value and its symmetries were applied
+#[derive(PartialEq, Clone, Debug)]
+struct Transition {
+	neighbors: [StateSet; 8], // In the order of rule::NEIGHBORS, every state for those outside of the neighborhood
+	next: u8,
+	permuted: bool // The neighbors match in any order
+}
+
+impl Transition {
+	fn matches(&self, neighbors: &[u8; 8], ring: &[usize]) -> bool {
+		if !self.permuted {
+			return ring.iter().all(|bit| self.neighbors[*bit].contains(neighbors[*bit]));
+		}
+
+		// Each neighbor has to take a different set that contains it
+		let mut owners = vec![None; ring.len()];
+		(0..ring.len()).all(|neighbor| assign(neighbor, &mut vec![false; ring.len()], &mut owners, &self.neighbors, neighbors, ring))
+	}
+}
+
+// Augmenting path of a matching between the neighbors and the sets of a permuted transition, moving the neighbors that took a set
+// to another one when possible
+fn assign(neighbor: usize, visited: &mut [bool], owners: &mut [Option<usize>], sets: &[StateSet; 8], neighbors: &[u8; 8], ring: &[usize]) -> bool {
+	for (index, bit) in ring.iter().enumerate() {
+		if visited[index] || !sets[*bit].contains(neighbors[ring[neighbor]]) {
+			continue;
+		}
+		visited[index] = true;
+		let free = match owners[index] {
+			Some(owner) => assign(owner, visited, owners, sets, neighbors, ring),
+			None => true
+		};
+		if free {
+			owners[index] = Some(neighbor);
+			return true;
+		}
+	}
+	false
+}
+
+// Golly rule table (https://golly.sourceforge.io/Help/formats.html#rule): the next state of a cell from its state and the
+// states of its neighbors, the first transition that matches. Cells that match none keep their state
+#[derive(PartialEq, Clone, Debug)]
+pub struct RuleTable {
+	pub name: String,
+	pub states: u8,
+	pub neighborhood: Neighborhood,
+	pub colors: Vec<Option<(u8, u8, u8)>>, // Of each state, from the @COLORS section
+	transitions: Vec<Vec<Transition>> // By the state of the cell, in the order of the file
+}
+
+impl RuleTable {
+	pub fn next(&self, state: u8, neighbors: &[u8; 8]) -> u8 {
+		let ring = ring(self.neighborhood);
+		match self.transitions.get(state as usize).and_then(|transitions| transitions.iter().find(|transition| transition.matches(neighbors, ring))) {
+			Some(transition) => transition.next,
+			None => state
+		}
+	}
+
+	// Parses the @TABLE and @COLORS sections of a .rule file, the others are ignored
+	pub fn parse(text: &str) -> Result<Self, String> {
+		let mut name = None;
+		let mut section = "";
+		let mut table_found = false;
+		let mut states = None;
+		let mut neighborhood = Neighborhood::Moore;
+		let mut symmetries = "none".to_string();
+		let mut variables: HashMap<String, StateSet> = HashMap::new();
+		let mut lines = Vec::new();
+		let mut color_lines = Vec::new();
+
+		for line in text.lines() {
+			let line = line.split('#').next().unwrap_or("").trim();
+			if line.is_empty() {
+				continue;
+			}
+			if let Some(header) = line.strip_prefix('@') {
+				let (header, value) = header.split_once(char::is_whitespace).unwrap_or((header, ""));
+				section = match header {
+					"RULE" => {
+						name = Some(value.trim().to_string());
+						"RULE"
+					},
+					"TABLE" => {
+						table_found = true;
+						"TABLE"
+					},
+					"COLORS" => "COLORS",
+					_ => ""
+				};
+				continue;
+			}
+
+			match section {
+				"TABLE" => {
+					if let Some((key, value)) = line.split_once(':') {
+						match key.trim() {
+							"n_states" => states = Some(value.trim().parse::<u8>().ok().filter(|states| *states >= 2)
+								.ok_or(format!("invalid amount of states \"{}\", it must be between 2 and 255", value.trim()))?),
+							"neighborhood" => neighborhood = match value.trim() {
+								"Moore" => Neighborhood::Moore,
+								"vonNeumann" => Neighborhood::VonNeumann,
+								"hexagonal" => Neighborhood::Hexagonal,
+								other => return Err(format!("the {} neighborhood is not supported", other))
+							},
+							"symmetries" => symmetries = value.trim().to_string(),
+							other => return Err(format!("unknown table setting \"{}\"", other))
+						}
+					} else if let Some(variable) = line.strip_prefix("var ") {
+						let (variable_name, values) = variable.split_once('=').ok_or(format!("invalid variable \"{}\"", line))?;
+						let values = parse_set(values.trim(), &variables)?;
+						variables.insert(variable_name.trim().to_string(), values);
+					} else {
+						lines.push(line.to_string());
+					}
+				},
+				"COLORS" => color_lines.push(line.to_string()),
+				_ => {}
+			}
+		}
+
+		if !table_found {
+			return Err("only rules with a @TABLE section are supported".to_string());
+		}
+		let states = states.ok_or("missing n_states in the table")?;
+		let ring = ring(neighborhood);
+		let symmetries = symmetry_permutations(symmetries.as_str(), ring.len())?;
+
+		let mut transitions = vec![Vec::new(); states as usize];
+		for line in lines {
+			// With 10 states or less each character can be a state, without commas
+			let tokens: Vec<String> = match line.contains(',') || line.contains('{') {
+				true => split_tokens(line.as_str()),
+				false => line.chars().filter(|character| !character.is_whitespace()).map(|character| character.to_string()).collect()
+			};
+			if tokens.len() != ring.len() + 2 {
+				return Err(format!("invalid transition \"{}\", expected {} states", line, ring.len() + 2));
+			}
+
+			// Variables used more than once are bound, every use has the same value
+			let mut bound: Vec<&String> = Vec::new();
+			for token in tokens.iter().filter(|token| variables.contains_key(token.as_str())) {
+				if tokens.iter().filter(|other| *other == token).count() > 1 && !bound.contains(&token) {
+					bound.push(token);
+				}
+			}
+			let mut assignments: Vec<Vec<u8>> = vec![Vec::new()];
+			for variable in bound.iter() {
+				assignments = assignments.into_iter()
+					.flat_map(|assignment| variables[variable.as_str()].iter().map(move |value| [assignment.clone(), vec![value]].concat()))
+					.collect();
+			}
+
+			for assignment in assignments {
+				let set = |token: &String| -> Result<StateSet, String> {
+					match bound.iter().position(|variable| *variable == token) {
+						Some(index) => Ok(StateSet::single(assignment[index])),
+						None => parse_set(token, &variables)
+					}
+				};
+				let next_set = set(&tokens[tokens.len() - 1])?;
+				let next = match next_set.iter().collect::<Vec<u8>>().as_slice() {
+					[next] => *next,
+					_ => return Err(format!("invalid transition \"{}\", the new state must be a single one", line))
+				};
+				let cell = set(&tokens[0])?;
+				let mut ring_sets = Vec::new();
+				for token in tokens[1..tokens.len() - 1].iter() {
+					ring_sets.push(set(token)?);
+				}
+				if cell.iter().chain(next_set.iter()).chain(ring_sets.iter().flat_map(|set| set.iter())).any(|state| state >= states) {
+					return Err(format!("invalid transition \"{}\", the table only has {} states", line, states));
+				}
+
+				let mut variants: Vec<Transition> = Vec::new();
+				for permutation in symmetries.iter() {
+					let mut neighbors = [StateSet::all(); 8];
+					for (index, set) in ring_sets.iter().enumerate() {
+						neighbors[ring[permutation.as_ref().map_or(index, |permutation| permutation[index])]] = *set;
+					}
+					let variant = Transition {
+						neighbors,
+						next,
+						permuted: permutation.is_none()
+					};
+					if !variants.contains(&variant) {
+						variants.push(variant);
+					}
+				}
+				for state in cell.iter() {
+					transitions[state as usize].extend(variants.iter().cloned());
+				}
+			}
+		}
+
+		// A line is either one color or a gradient from the first state to the last one
+		let mut colors = vec![None; states as usize];
+		for line in color_lines {
+			let values: Vec<u8> = line.split_whitespace().map(|value| value.parse::<u8>()).collect::<Result<_, _>>()
+				.map_err(|_| format!("invalid color \"{}\"", line))?;
+			match values.as_slice() {
+				[state, r, g, b] if *state < states => colors[*state as usize] = Some((*r, *g, *b)),
+				[r1, g1, b1, r2, g2, b2] => {
+					for state in 1..states {
+						let fade = if states > 2 { (state - 1) as f32 / (states - 2) as f32 } else { 0.0 };
+						let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * fade).round() as u8;
+						colors[state as usize] = Some((mix(*r1, *r2), mix(*g1, *g2), mix(*b1, *b2)));
+					}
+				},
+				_ => return Err(format!("invalid color \"{}\"", line))
+			}
+		}
+
+		Ok(RuleTable {
+			name: name.filter(|name| !name.is_empty()).unwrap_or_else(|| "Table".to_string()),
+			states,
+			neighborhood,
+			colors,
+			transitions
+		})
+	}
+}
+
+// Bits of rule::NEIGHBORS in the neighborhood, in the order of the table: clockwise from the one above
+fn ring(neighborhood: Neighborhood) -> &'static [usize] {
+	match neighborhood {
+		Neighborhood::Moore => &[0, 1, 2, 3, 4, 5, 6, 7],
+		Neighborhood::VonNeumann => &[0, 2, 4, 6],
+		Neighborhood::Hexagonal => &[0, 2, 3, 4, 6, 7]
+	}
+}
+
+// Where each neighbor of the ring goes in every symmetric copy of a transition. None for permute, which is matched in any order
+fn symmetry_permutations(symmetries: &str, size: usize) -> Result<Vec<Option<Vec<usize>>>, String> {
+	let rotated = |steps: usize| (0..size).map(|index| (index + steps) % size).collect::<Vec<usize>>();
+	let mirrored = |permutation: &Vec<usize>| permutation.iter().map(|index| (size - index) % size).collect::<Vec<usize>>();
+	let rotations = |amount: usize| -> Result<Vec<Vec<usize>>, String> {
+		match size % amount {
+			0 => Ok((0..amount).map(|rotation| rotated(rotation * size / amount)).collect()),
+			_ => Err(format!("the {} symmetries don't fit the neighborhood", symmetries))
+		}
+	};
+
+	let permutations = match symmetries {
+		"none" => vec![rotated(0)],
+		"permute" => return Ok(vec![None]),
+		"reflect_horizontal" => vec![rotated(0), mirrored(&rotated(0))],
+		_ => {
+			let (amount, reflect) = match symmetries.strip_prefix("rotate") {
+				Some(rest) => (rest.trim_end_matches("reflect"), rest.ends_with("reflect")),
+				None => return Err(format!("unknown symmetries \"{}\"", symmetries))
+			};
+			let amount = amount.parse::<usize>().ok().filter(|amount| *amount > 0).ok_or(format!("unknown symmetries \"{}\"", symmetries))?;
+			let mut permutations = rotations(amount)?;
+			if reflect {
+				permutations.extend(permutations.clone().iter().map(mirrored));
+			}
+			permutations
+		}
+	};
+	Ok(permutations.into_iter().map(Some).collect())
+}
+
+// Tokens of a transition separated by commas, those between braces are a single one
+fn split_tokens(line: &str) -> Vec<String> {
+	let mut tokens = vec![String::new()];
+	let mut depth = 0;
+	for character in line.chars().filter(|character| !character.is_whitespace()) {
+		match character {
+			',' if depth == 0 => tokens.push(String::new()),
+			_ => {
+				depth += (character == '{') as i32 - (character == '}') as i32;
+				tokens.last_mut().unwrap().push(character);
+			}
+		}
+	}
+	tokens
+}
+
+// A state, a variable or a set of them between braces
+fn parse_set(token: &str, variables: &HashMap<String, StateSet>) -> Result<StateSet, String> {
+	if let Some(variable) = variables.get(token) {
+		return Ok(*variable);
+	}
+	if let Ok(state) = token.parse::<u8>() {
+		return Ok(StateSet::single(state));
+	}
+	let values = token.strip_prefix('{').and_then(|token| token.strip_suffix('}')).ok_or(format!("unknown variable \"{}\"", token))?;
+	let mut set = StateSet::empty();
+	for value in split_tokens(values) {
+		for state in parse_set(value.as_str(), variables)?.iter() {
+			set.insert(state);
+		}
+	}
+	Ok(set)
+}
+
+pub fn load(path: &str) -> Result<Rule, String> {
+	let text = fs::read_to_string(path).map_err(|error| format!("could not read {}: {}", path, error))?;
+	Ok(Rule::from_table(Arc::new(RuleTable::parse(text.as_str())?)))
+}
+
+// Where the rule table of that name (e.g. WireWorld.rule) is looked for: the current folder, then the rules one
+fn table_paths(name: &str) -> [PathBuf; 2] {
+	[Path::new(".").join(format!("{}.rule", name)), Path::new(RULES_FOLDER).join(format!("{}.rule", name))]
+}
+
+// The rule table of that name, in the first of the table paths where it exists
+pub fn find(name: &str) -> Option<Result<Rule, String>> {
+	table_paths(name).iter()
+		.find(|path| path.is_file())
+		.map(|path| load(path.to_string_lossy().as_ref()))
+}
+
+// A rulestring, or else the name of a rule table. Names that can't be rulestrings report where the table was looked for
+pub fn parse_rule(text: &str) -> Result<Rule, String> {
+	Rule::parse(text).or_else(|error| match find(text) {
+		Some(rule) => rule,
+		None if text.chars().all(|character| character.is_alphanumeric() || character == '_' || character == '-') => {
+			let paths: Vec<String> = table_paths(text).iter().map(|path| path.to_string_lossy().to_string()).collect();
+			Err(format!("unknown rule \"{}\", there is no rule table at {}", text, paths.join(" or ")))
+		},
+		None => Err(error)
+	})
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Table with a single transition that gives birth to state 1
+	fn birth_table(neighborhood: &str, symmetries: &str, transition: &str) -> RuleTable {
+		RuleTable::parse(format!("@TABLE\nn_states:2\nneighborhood:{}\nsymmetries:{}\n{}\n", neighborhood, symmetries, transition).as_str()).unwrap()
+	}
+
+	// Next state of an empty cell, with the neighbors in the order of the table
+	fn next_of_empty(table: &RuleTable, ring_states: &[u8]) -> u8 {
+		let mut neighbors = [0; 8];
+		for (index, bit) in ring(table.neighborhood).iter().enumerate() {
+			neighbors[*bit] = ring_states[index];
+		}
+		table.next(0, &neighbors)
+	}
+
+	#[test]
+	fn symmetries_of_the_moore_neighborhood() {
+		// The neighbor above and the one at its right
+		let none = birth_table("Moore", "none", "0,1,1,0,0,0,0,0,0,1");
+		assert_eq!(next_of_empty(&none, &[1, 1, 0, 0, 0, 0, 0, 0]), 1);
+		assert_eq!(next_of_empty(&none, &[0, 0, 1, 1, 0, 0, 0, 0]), 0);
+
+		let rotate4 = birth_table("Moore", "rotate4", "0,1,1,0,0,0,0,0,0,1");
+		assert_eq!(next_of_empty(&rotate4, &[0, 0, 1, 1, 0, 0, 0, 0]), 1);
+		assert_eq!(next_of_empty(&rotate4, &[0, 0, 0, 0, 0, 1, 1, 0]), 0);
+		assert_eq!(next_of_empty(&rotate4, &[1, 0, 0, 0, 0, 0, 0, 1]), 0);
+
+		let rotate8 = birth_table("Moore", "rotate8", "0,1,1,0,0,0,0,0,0,1");
+		assert_eq!(next_of_empty(&rotate8, &[0, 0, 0, 0, 0, 1, 1, 0]), 1);
+		assert_eq!(next_of_empty(&rotate8, &[1, 0, 0, 0, 0, 0, 0, 1]), 1);
+		assert_eq!(next_of_empty(&rotate8, &[1, 0, 1, 0, 0, 0, 0, 0]), 0);
+
+		let rotate4reflect = birth_table("Moore", "rotate4reflect", "0,1,1,0,0,0,0,0,0,1");
+		assert_eq!(next_of_empty(&rotate4reflect, &[1, 0, 0, 0, 0, 0, 0, 1]), 1);
+		assert_eq!(next_of_empty(&rotate4reflect, &[0, 0, 0, 0, 1, 1, 0, 0]), 1);
+		assert_eq!(next_of_empty(&rotate4reflect, &[0, 1, 1, 0, 0, 0, 0, 0]), 1);
+		assert_eq!(next_of_empty(&rotate4reflect, &[1, 0, 1, 0, 0, 0, 0, 0]), 0);
+
+		// Mirrored left to right, the neighbor above stays in place
+		let reflect = birth_table("Moore", "reflect_horizontal", "0,0,1,0,0,0,0,0,0,1");
+		assert_eq!(next_of_empty(&reflect, &[0, 0, 0, 0, 0, 0, 0, 1]), 1);
+		assert_eq!(next_of_empty(&reflect, &[0, 0, 0, 1, 0, 0, 0, 0]), 0);
+
+		let permute = birth_table("Moore", "permute", "0,1,1,0,0,0,0,0,0,1");
+		assert_eq!(next_of_empty(&permute, &[0, 0, 1, 0, 0, 0, 1, 0]), 1);
+		assert_eq!(next_of_empty(&permute, &[0, 0, 1, 0, 0, 0, 0, 0]), 0);
+		assert_eq!(next_of_empty(&permute, &[1, 0, 1, 0, 0, 0, 1, 0]), 0);
+	}
+
+	#[test]
+	fn symmetries_of_the_other_neighborhoods() {
+		let von_neumann = birth_table("vonNeumann", "rotate4", "0,1,0,0,0,1");
+		for ring_states in [[1, 0, 0, 0], [0, 1, 0, 0], [0, 0, 1, 0], [0, 0, 0, 1]] {
+			assert_eq!(next_of_empty(&von_neumann, &ring_states), 1);
+		}
+		assert_eq!(next_of_empty(&von_neumann, &[1, 1, 0, 0]), 0);
+		let von_neumann = birth_table("vonNeumann", "reflect_horizontal", "0,0,1,0,0,1");
+		assert_eq!(next_of_empty(&von_neumann, &[0, 0, 0, 1]), 1);
+		assert_eq!(next_of_empty(&von_neumann, &[0, 0, 1, 0]), 0);
+
+		// Every other neighbor of the hexagon
+		let rotate3 = birth_table("hexagonal", "rotate3", "0,1,0,0,0,0,0,1");
+		assert_eq!(next_of_empty(&rotate3, &[0, 0, 1, 0, 0, 0]), 1);
+		assert_eq!(next_of_empty(&rotate3, &[0, 0, 0, 0, 1, 0]), 1);
+		assert_eq!(next_of_empty(&rotate3, &[0, 1, 0, 0, 0, 0]), 0);
+		let rotate6reflect = birth_table("hexagonal", "rotate6reflect", "0,1,1,0,0,0,0,1");
+		assert_eq!(next_of_empty(&rotate6reflect, &[0, 0, 0, 1, 1, 0]), 1);
+		assert_eq!(next_of_empty(&rotate6reflect, &[1, 0, 0, 0, 0, 1]), 1);
+		assert_eq!(next_of_empty(&rotate6reflect, &[1, 0, 1, 0, 0, 0]), 0);
+
+		assert!(RuleTable::parse("@TABLE\nn_states:2\nneighborhood:hexagonal\nsymmetries:rotate4\n").is_err());
+		assert!(RuleTable::parse("@TABLE\nn_states:2\nneighborhood:Moore\nsymmetries:rotate5\n").is_err());
+	}
+
+	#[test]
+	fn bound_variables() {
+		// The two neighbors above have the same state, whichever it is
+		let table = RuleTable::parse("@TABLE\nn_states:3\nneighborhood:vonNeumann\nsymmetries:none\nvar a={1,2}\nvar b={0,1,2}\n0,a,a,b,0,1\n0,a,0,0,0,a\n").unwrap();
+		let next = |ring_states: [u8; 4]| next_of_empty(&table, &ring_states);
+		assert_eq!(next([1, 1, 2, 0]), 1);
+		assert_eq!(next([2, 2, 0, 0]), 1);
+		assert_eq!(next([1, 2, 0, 0]), 0);
+		// The new state takes the value of the neighbor
+		assert_eq!(next([1, 0, 0, 0]), 1);
+		assert_eq!(next([2, 0, 0, 0]), 2);
+		assert_eq!(next([0, 2, 0, 0]), 0);
+	}
+
+	#[test]
+	fn transitions_without_commas() {
+		let header = "@TABLE\nn_states:3\nneighborhood:vonNeumann\nsymmetries:rotate4\n";
+		let with_commas = RuleTable::parse(format!("{}0,1,2,0,0,1\n1,0,0,0,0,2\n", header).as_str()).unwrap();
+		let without_commas = RuleTable::parse(format!("{}012001\n1 0 0 0 0 2\n", header).as_str()).unwrap();
+		assert_eq!(with_commas, without_commas);
+		assert!(RuleTable::parse(format!("{}01200\n", header).as_str()).is_err());
+	}
+
+	#[test]
+	fn colors() {
+		let table = RuleTable::parse("@TABLE\nn_states:3\n@COLORS\n0 0 0 0\n1 255 0 0\n").unwrap();
+		assert_eq!(table.colors, vec![Some((0, 0, 0)), Some((255, 0, 0)), None]);
+
+		// A gradient from the first alive state to the last one
+		let table = RuleTable::parse("@TABLE\nn_states:4\n@COLORS\n0 0 0 255 255 255\n").unwrap();
+		assert_eq!(table.colors, vec![None, Some((0, 0, 0)), Some((128, 128, 128)), Some((255, 255, 255))]);
+
+		assert!(RuleTable::parse("@TABLE\nn_states:2\n@COLORS\n1 2\n").is_err());
+		assert!(RuleTable::parse("@TABLE\nn_states:2\n@COLORS\n1 300 0 0\n").is_err());
+	}
+
+	#[test]
+	fn wireworld() {
+		let table = RuleTable::parse(include_str!("../rules/WireWorld.rule")).unwrap();
+		assert_eq!((table.name.as_str(), table.states), ("WireWorld", 4));
+
+		// Heads turn into tails, and tails back into conductor
+		assert_eq!(table.next(1, &[3, 3, 0, 0, 0, 0, 0, 2]), 2);
+		assert_eq!(table.next(2, &[3, 1, 0, 0, 0, 0, 0, 0]), 3);
+		// Conductor becomes a head with 1 or 2 heads around, in any place
+		assert_eq!(table.next(3, &[0, 0, 0, 0, 0, 0, 0, 0]), 3);
+		assert_eq!(table.next(3, &[0, 0, 0, 2, 1, 3, 0, 0]), 1);
+		assert_eq!(table.next(3, &[1, 0, 0, 0, 0, 0, 3, 1]), 1);
+		assert_eq!(table.next(3, &[1, 0, 1, 0, 1, 0, 0, 0]), 3);
+		assert_eq!(table.next(0, &[1, 1, 0, 0, 0, 0, 0, 0]), 0);
+	}
+
+	#[test]
+	fn missing_tables_report_where_they_were_looked_for() {
+		let error = parse_rule("NoSuchTable").unwrap_err();
+		assert!(error.contains("NoSuchTable.rule"), "{}", error);
+		assert!(error.contains("rules"), "{}", error);
+		// Text that can only be a rulestring keeps the error of the notation
+		assert!(parse_rule("B3/S23/C").unwrap_err().contains("B3/S23/C"));
+	}
+}
@@ -1,16 +1,14 @@
 extern crate sdl2;
-extern crate rayon;
 
-use rayon::iter::IndexedParallelIterator;
-use rayon::iter::IntoParallelRefMutIterator;
-use rayon::iter::ParallelIterator;
-use sdl2::sys::_Float32;
 use core::fmt;
+use std::time::Duration;
 use std::time::Instant;
 
 use super::SUB_MATRIX_CHUNK_SIZE;
 
 use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::keyboard::Mod;
 use sdl2::mouse::MouseButton;
 use sdl2::pixels::Color;
 use sdl2::rect::Point;
@@ -18,11 +16,33 @@ use sdl2::rect::Rect;
 use sdl2::render::TextureQuery;
 use sdl2::ttf::Font;
 
-use crate::SUB_MATRIX_SIZE;
 use crate::button;
 use crate::button::Button;
 use crate::button_icon;
 use crate::button_icon::ButtonIcon;
+use crate::census::Census;
+use crate::classification::Classifier;
+use crate::engine::BoundingBox;
+use crate::engine::ChunkEvaluation;
+use crate::engine::Engine;
+use crate::engine::IterationStats;
+use crate::heatmap::Heatmap;
+use crate::image_export;
+use crate::image_export::Scale;
+use crate::minimap::Minimap;
+use crate::predecessor;
+use crate::rle;
+use crate::rule_editor::RuleEditor;
+use crate::rule_editor::RuleEditorAction;
+use crate::rule_table;
+use crate::rule::Neighborhood;
+use crate::rule::Rule;
+use crate::statistics::Record;
+use crate::statistics::Statistics;
+use crate::stochastic::Stochastic;
+use crate::stop_conditions::StopConditions;
+use crate::stop_conditions::StopReason;
+use crate::svg_export;
 
 
 
@@ -35,6 +55,17 @@ const BTN_SQUARE_SIZE: u32 = BTN_HEIGHT;
 const BTN_SQUARE_H_MARGIN: u32 = 5;
 const GRID_BOTTOM_MARGIN: u32 = 5;
 
+const NEIGHBOR_COUNT_MIN_SIZE: u32 = 10; // Minimum cell size (in pixels) required to print the neighbor count inside each cell
+const OVERLAY_ALPHA: u8 = 140;
+const HEATMAP_ALPHA: u8 = 170;
+const DEBUG_ALPHA: u8 = 60;
+const REGION_ALPHA: u8 = 50;
+const REGION_COLORS: [Color; 4] = [crate::COLOR_BLUE, crate::COLOR_YELLOW, crate::COLOR_RED, crate::COLOR_GREEN]; // Tint of each region rule
+const DEBUG_PANEL_PADDING: u32 = 5;
+const STATS_MARGIN: u32 = 10;
+const STATS_GRAPH_HEIGHT: u32 = 120;
+const CENSUS_MAX_LINES: usize = 20;
+
 
 #[derive(PartialEq)]
 enum Tool {
@@ -74,18 +105,32 @@ pub struct Scene<'scene> {
 	top_left_row: u32,
 	
 	state: State,
-	generation: Vec<Vec<bool>>,
+	engine: Engine,
 	previous_generation: Vec<Vec<bool>>,
-	change_matrix: Vec<Vec<bool>>,
-	generation_number: i32,
-	population_amount: u32,
+	previous_states: Vec<Vec<u8>>, // Dying states of the starting generation, for rules that have them
+	last_step_generation: Option<Vec<Vec<bool>>>, // Generation before the last iteration, used to highlight births and deaths
+	heatmap: Heatmap,
+	statistics: Statistics,
+	classifier: Classifier,
+	stop_conditions: StopConditions,
+	auto_stop: bool,
+	stop_reason: Option<StopReason>,
+	notice: Option<String>, // Result of the last action, shown in the side panel
+	visible_population: u32,
 	last_iteration: Instant,
+	last_iteration_stats: Option<IterationStats>,
+
+	render_duration: Duration,
+	frame_count: u32,
+	fps: u32,
+	fps_timer: Instant,
 
 	btn_start_simulation: Button,
 	btn_pause_resume_simulation: Button,
 	btn_abort_simulation: Button,
 	btn_abort_n_save_simulation: Button,
 	btn_clear_generation: Button,
+	btn_step_simulation: Button,
 
 	btn_tool_pencil: ButtonIcon,
 	btn_tool_eraser: ButtonIcon,
@@ -93,17 +138,36 @@ pub struct Scene<'scene> {
 
 	dragging: bool,
 	dragging_start: (i32, i32),
+	paint_state: u8, // State the pencil draws, alive unless the rule has dying states
+
+	minimap: Minimap,
+	dragging_minimap: bool,
+
+	selection: Option<BoundingBox>,
+	selection_start: Option<(u32, u32)>,
 
-	main_font: Option<Font<'scene, 'static>>
+	census: Option<Census>,
+	census_generation: i32,
+
+	rule_editor: Option<RuleEditor>, // Open over the grid, it gets every event
+
+	show_changes_overlay: bool,
+	show_neighbor_counts: bool,
+	show_heatmap: bool,
+	show_debug_overlay: bool,
+
+	main_font: Option<Font<'scene, 'static>>,
+	small_font: Option<Font<'scene, 'static>>
 }
 
 impl<'s> Scene<'s> {
-	pub fn new(canvas: sdl2::render::Canvas<sdl2::video::Window>) -> Self {
+	pub fn new(canvas: sdl2::render::Canvas<sdl2::video::Window>, stop_conditions: StopConditions, stochastic: Option<Stochastic>) -> Self {
 		let texture_creator = canvas.texture_creator();
 	
-		let generation = vec![vec![false; crate::MATRIX_SIZE as usize]; crate::MATRIX_SIZE as usize];
 		let previous_generation = vec![vec![false; crate::MATRIX_SIZE as usize]; crate::MATRIX_SIZE as usize];
-		let change_matrix = vec![vec![true; crate::SUB_MATRIX_SIZE as usize]; crate::SUB_MATRIX_SIZE as usize];
+		let mut engine = Engine::new();
+		// The engine starts with Life, which supports any probabilities
+		engine.set_stochastic(stochastic).unwrap();
 	
 		Self {
 			canvas: canvas,
@@ -115,18 +179,32 @@ impl<'s> Scene<'s> {
 			top_left_row: 20,
 
 			state: State::IDLE,
-			generation,
+			engine,
 			previous_generation,
-			change_matrix,
-			generation_number: 0,
-			population_amount: 0,
+			previous_states: Vec::new(),
+			last_step_generation: None,
+			heatmap: Heatmap::new(),
+			statistics: Statistics::new(),
+			classifier: Classifier::new(None),
+			stop_conditions,
+			auto_stop: true,
+			stop_reason: None,
+			notice: None,
+			visible_population: 0,
 			last_iteration: std::time::Instant::now(),
+			last_iteration_stats: None,
+
+			render_duration: Duration::ZERO,
+			frame_count: 0,
+			fps: 0,
+			fps_timer: Instant::now(),
 
 			btn_start_simulation:	button::Button::new(crate::COLOR_GREEN, Rect::new(crate::H_MARGIN as i32, (crate::V_MARGIN + GRID_BOTTOM_MARGIN + crate::GRID_HEIGHT) as i32, BTN_WIDTH, BTN_HEIGHT), "Start".to_string()),
 			btn_pause_resume_simulation:	button::Button::new(crate::COLOR_YELLOW,Rect::new(crate::H_MARGIN as i32, (crate::V_MARGIN + GRID_BOTTOM_MARGIN + crate::GRID_HEIGHT) as i32, BTN_WIDTH, BTN_HEIGHT), "Pause".to_string()),
 			btn_abort_simulation:	button::Button::new(crate::COLOR_RED,	Rect::new((crate::H_MARGIN + BTN_WIDTH+BTN_H_MARGIN) as i32, (crate::V_MARGIN + GRID_BOTTOM_MARGIN + crate::GRID_HEIGHT) as i32, BTN_WIDTH, BTN_HEIGHT), "Abort".to_string()),
 			btn_abort_n_save_simulation:	button::Button::new(crate::COLOR_RED,	Rect::new((crate::H_MARGIN + (BTN_WIDTH+BTN_H_MARGIN)*2) as i32, (crate::V_MARGIN + GRID_BOTTOM_MARGIN + crate::GRID_HEIGHT) as i32, BTN_ABORT_N_SAVE_WIDTH, BTN_HEIGHT), "Abort and save state".to_string()),
 			btn_clear_generation:	button::Button::new(crate::COLOR_BLUE,	Rect::new((crate::H_MARGIN + crate::GRID_WIDTH - BTN_CLEAR_WIDTH) as i32, (crate::V_MARGIN + GRID_BOTTOM_MARGIN + crate::GRID_HEIGHT) as i32, BTN_CLEAR_WIDTH, BTN_HEIGHT), "Clear population".to_string()),
+			btn_step_simulation:	button::Button::new(crate::COLOR_GREEN,	Rect::new((crate::H_MARGIN + (BTN_WIDTH+BTN_H_MARGIN)*2 + BTN_ABORT_N_SAVE_WIDTH + BTN_H_MARGIN) as i32, (crate::V_MARGIN + GRID_BOTTOM_MARGIN + crate::GRID_HEIGHT) as i32, BTN_WIDTH, BTN_HEIGHT), "Step".to_string()),
 
 			btn_tool_pencil: 	button_icon::ButtonIcon::new(Rect::new((crate::H_MARGIN + crate::GRID_WIDTH - BTN_CLEAR_WIDTH - BTN_H_MARGIN - BTN_SQUARE_SIZE*3 - BTN_SQUARE_H_MARGIN*2) as i32, (crate::V_MARGIN + GRID_BOTTOM_MARGIN + crate::GRID_HEIGHT) as i32, BTN_SQUARE_SIZE, BTN_SQUARE_SIZE), "./icons/pencil.bmp".to_string()),
 			btn_tool_eraser:	button_icon::ButtonIcon::new(Rect::new((crate::H_MARGIN + crate::GRID_WIDTH - BTN_CLEAR_WIDTH - BTN_H_MARGIN - BTN_SQUARE_SIZE*2 - BTN_SQUARE_H_MARGIN) as i32, (crate::V_MARGIN + GRID_BOTTOM_MARGIN + crate::GRID_HEIGHT) as i32, BTN_SQUARE_SIZE, BTN_SQUARE_SIZE), "./icons/eraser.bmp".to_string()),
@@ -134,29 +212,156 @@ impl<'s> Scene<'s> {
 
 			dragging: false,
 			dragging_start: (-1, -1),
+			paint_state: 1,
 
-			main_font: None
+			minimap: Minimap::new((crate::H_MARGIN * 2 + crate::GRID_WIDTH) as i32, crate::V_MARGIN as i32),
+			dragging_minimap: false,
+
+			selection: None,
+			selection_start: None,
+
+			census: None,
+			census_generation: 0,
+
+			rule_editor: None,
+
+			show_changes_overlay: false,
+			show_neighbor_counts: false,
+			show_heatmap: false,
+			show_debug_overlay: false,
+
+			main_font: None,
+			small_font: None
 		}
 	}
 	
-	pub fn initialize(&mut self, font: Font<'s, 'static>) {
+	pub fn initialize(&mut self, font: Font<'s, 'static>, small_font: Font<'s, 'static>) {
 		self.set_state(State::IDLE);
 		self.set_tool(Tool::PENCIL);
 
 		self.main_font = Some(font);
+		self.small_font = Some(small_font);
 		self.load_start_generation();
+		self.minimap.refresh_all(self.engine.generation());
 	}
 
 	pub fn handle_events<'h>(&mut self, event_iterator: sdl2::event::EventPollIterator<'h>) -> bool {
 		for event in event_iterator {
+			if let Event::Quit { .. } = event {
+				return true;
+			}
+			if let Some(editor) = self.rule_editor.as_mut() {
+				match editor.handle_event(&event) {
+					Some(RuleEditorAction::Apply(rule)) => {
+						println!("[INFO] Switched to the rule {}", rule);
+						self.rule_editor = None;
+						self.switch_rule(*rule);
+					},
+					Some(RuleEditorAction::ApplyToSelection(rule)) => {
+						self.rule_editor = None;
+						self.set_region_rule(*rule);
+					},
+					Some(RuleEditorAction::Close) => self.rule_editor = None,
+					None => {}
+				}
+				continue;
+			}
 			match event {
-				Event::Quit { .. } => return true,
+				Event::KeyDown { keycode: Some(keycode), keymod, repeat: false, .. } => {
+					match keycode {
+						Keycode::H if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) => {
+							let path = format!("heatmap_{}.bmp", self.engine.generation_number());
+							match self.heatmap.export_bmp(path.as_str()) {
+								Ok(()) => println!("[INFO] Heatmap saved to {}", path),
+								Err(error) => println!("[ERROR] Could not save the heatmap: {}", error)
+							}
+						},
+						Keycode::H => self.show_heatmap = !self.show_heatmap,
+						Keycode::C if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) => {
+							let path = format!("statistics_{}.csv", self.engine.generation_number());
+							match self.statistics.export_csv(path.as_str()) {
+								Ok(()) => println!("[INFO] Statistics saved to {}", path),
+								Err(error) => println!("[ERROR] Could not save the statistics: {}", error)
+							}
+						},
+						Keycode::C => self.toggle_census(),
+						Keycode::I if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) => {
+							let path = format!("pattern_{}.png", self.engine.generation_number());
+							match image_export::save_pattern(&self.engine, path.as_str(), None, true) {
+								Ok(()) => println!("[INFO] Pattern image saved to {}", path),
+								Err(error) => println!("[ERROR] Could not save the pattern image: {}", error)
+							}
+						},
+						Keycode::I if self.hexagonal() => println!("[ERROR] Could not save the view image: hexagonal rules are shown sheared, save the pattern with Shift+I instead"),
+						Keycode::I => {
+							let path = format!("view_{}.png", self.engine.generation_number());
+							let view = BoundingBox {
+								top: self.top_left_row,
+								left: self.top_left_col,
+								bottom: self.top_left_row + crate::VIEW_ROWS - 1,
+								right: self.top_left_col + crate::VIEW_COLS - 1
+							};
+							match image_export::render(&self.engine, view, Scale::PixelsPerCell(crate::SIZE), true).and_then(|image| image.save_png(path.as_str())) {
+								Ok(()) => println!("[INFO] View image saved to {}", path),
+								Err(error) => println!("[ERROR] Could not save the view image: {}", error)
+							}
+						},
+						Keycode::V => {
+							// Shift also labels each object with its name
+							let labels = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+							let path = format!("pattern_{}.svg", self.engine.generation_number());
+							match svg_export::save(&self.engine, self.selection, path.as_str(), true, labels) {
+								Ok(()) => println!("[INFO] Vector image saved to {}", path),
+								Err(error) => println!("[ERROR] Could not save the vector image: {}", error)
+							}
+						},
+						Keycode::R => {
+							let path = format!("pattern_{}.rle", self.engine.generation_number());
+							let comments = vec![format!("Generation {}", self.engine.generation_number())];
+							match self.engine.to_pattern(self.selection).ok_or("there are no alive cells".to_string()).and_then(|pattern| rle::save(path.as_str(), &pattern, &comments)) {
+								Ok(()) => println!("[INFO] Pattern saved to {}", path),
+								Err(error) => println!("[ERROR] Could not save the pattern: {}", error)
+							}
+						},
+						Keycode::D => self.show_debug_overlay = !self.show_debug_overlay,
+						Keycode::Escape => self.set_selection(None),
+						Keycode::A => self.auto_stop = !self.auto_stop,
+						Keycode::P if self.state != State::ITERATING => self.find_predecessor(),
+						Keycode::E if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) => {
+							self.engine.clear_region_rules();
+							self.notice = Some(format!("{} everywhere", self.engine.rule()));
+						},
+						Keycode::E if self.state != State::ITERATING => self.rule_editor = Some(RuleEditor::new(self.engine.rule(), self.selection.is_some())),
+						Keycode::O => self.show_changes_overlay = !self.show_changes_overlay,
+						Keycode::N => self.show_neighbor_counts = !self.show_neighbor_counts,
+						Keycode::Num1 | Keycode::Num2 | Keycode::Num3 | Keycode::Num4 | Keycode::Num5 | Keycode::Num6 | Keycode::Num7 | Keycode::Num8 | Keycode::Num9 => {
+							// The pencil paints alive cells (1), one of the dying states of the rule or one of its species
+							let state = keycode as i32 - Keycode::Num0 as i32;
+							if state < self.engine.rule().states as i32 {
+								self.paint_state = state as u8;
+							}
+						},
+						Keycode::S if self.state != State::ITERATING => {
+							self.step_simulation();
+							self.update_btn_visibility();
+						},
+						_ => {}
+					}
+				},
 				Event::MouseButtonUp { .. } => {
-					self.dragging = false
+					self.dragging = false;
+					self.dragging_minimap = false;
+					self.selection_start = None;
 				},
 				Event::MouseButtonDown { x, y, mouse_btn, .. } => {
 					match mouse_btn {
 						MouseButton::Left => {
+							if self.minimap.contains(x, y) {
+								self.dragging_minimap = true;
+								let (row, col) = self.minimap.get_cell(x, y);
+								self.center_view_on(row, col);
+							}
+
 							if !self.dragging {
 								if self.active_tool == Tool::HAND {
 									let (_, clicked_canvas) = get_click_indexes(x, y);
@@ -167,46 +372,55 @@ impl<'s> Scene<'s> {
 								}
 		
 								if self.state != State::ITERATING { // Allow draw on Idle or Pause states
-									let (indexes, clicked_canvas) = get_click_indexes(x, y);
-									if clicked_canvas {
+									if let Some((row, col)) = self.clicked_cell(x, y) {
 										if self.active_tool == Tool::PENCIL {
-											self.generation[(self.top_left_row as i32 + indexes.0) as usize][(self.top_left_col as i32 + indexes.1)  as usize] = true;
+											self.set_cell(row, col, self.paint_state);
 										} else if self.active_tool == Tool::ERASER {
-											self.generation[(self.top_left_row as i32 + indexes.0) as usize][(self.top_left_col as i32 + indexes.1)  as usize] = false;
+											self.set_cell(row, col, 0);
 										}
 									}
 								}
 		
 								if self.btn_start_simulation.is_hovered() {
 									self.set_state(State::ITERATING);
-									self.previous_generation = self.generation.clone();
+									self.start_run();
 									self.btn_pause_resume_simulation.set_text("Pause".to_string());
-									self.bruteforce_changes();
+									self.engine.bruteforce_changes();
 									self.set_tool(Tool::HAND);
 								} else if self.btn_pause_resume_simulation.is_hovered() {	
 									if self.state == State::PAUSE {
+										if self.stop_reason.take().is_some() { // Resuming after an automatic stop, keep going until the user stops it
+											self.auto_stop = false;
+										}
 										self.set_state(State::ITERATING);
 										self.btn_pause_resume_simulation.set_text("Pause".to_string());
-										self.bruteforce_changes();
+										self.engine.bruteforce_changes();
 										self.set_tool(Tool::HAND);
 									} else if self.state == State::ITERATING {
 										self.set_state(State::PAUSE);
 										self.btn_pause_resume_simulation.set_text("Resume".to_string());
 										self.set_tool(Tool::PENCIL);
 									}
+								} else if self.btn_step_simulation.is_hovered() {
+									self.step_simulation();
 								} else if self.btn_abort_simulation.is_hovered() {
+									self.engine.set_generation(self.previous_generation.clone(), self.previous_states.clone());
+									self.engine.reset();
 									self.set_state(State::IDLE);
-									self.generation = self.previous_generation.clone();
-									self.change_matrix = vec![vec![true; crate::SUB_MATRIX_SIZE as usize]; crate::SUB_MATRIX_SIZE as usize];
-									self.generation_number = 0;
+									self.last_step_generation = None;
+									self.stop_reason = None;
+									self.minimap.refresh_all(self.engine.generation());
 									self.set_tool(Tool::PENCIL);
 								} else if self.btn_abort_n_save_simulation.is_hovered() {
+									self.engine.reset();
 									self.set_state(State::IDLE);
-									self.change_matrix = vec![vec![true; crate::SUB_MATRIX_SIZE as usize]; crate::SUB_MATRIX_SIZE as usize];
-									self.generation_number = 0;
+									self.last_step_generation = None;
+									self.stop_reason = None;
 									self.set_tool(Tool::PENCIL);
 								} else if self.btn_clear_generation.is_hovered() {
-									self.generation = vec![vec![false; crate::MATRIX_SIZE as usize]; crate::MATRIX_SIZE as usize];
+									self.engine.clear();
+									self.last_step_generation = None;
+									self.minimap.clear();
 								} else if self.btn_tool_pencil.is_hovered() {
 									self.set_tool(Tool::PENCIL);
 								} else if self.btn_tool_eraser.is_hovered() {
@@ -214,31 +428,8 @@ impl<'s> Scene<'s> {
 								} else if self.btn_tool_hand.is_hovered() {
 									self.set_tool(Tool::HAND);
 								}
-								
-								if self.state != State::ITERATING {
-									if self.generation_number == 0 {
-										self.btn_start_simulation.set_hidden(false);
-										self.btn_pause_resume_simulation.set_hidden(true);
-										self.btn_abort_simulation.set_hidden(true);
-										self.btn_abort_n_save_simulation.set_hidden(true);
-									} else {
-										self.btn_start_simulation.set_hidden(true);
-										self.btn_pause_resume_simulation.set_hidden(false);
-										self.btn_abort_simulation.set_hidden(false);
-										self.btn_abort_n_save_simulation.set_hidden(false);
-									}
-									self.btn_clear_generation.set_hidden(false);
-									self.btn_tool_pencil.set_hidden(false);
-									self.btn_tool_eraser.set_hidden(false);
-								} else {
-									self.btn_start_simulation.set_hidden(true);
-									self.btn_clear_generation.set_hidden(true);
-									self.btn_pause_resume_simulation.set_hidden(false);
-									self.btn_abort_simulation.set_hidden(false);
-									self.btn_abort_n_save_simulation.set_hidden(false);
-									self.btn_tool_pencil.set_hidden(true);
-									self.btn_tool_eraser.set_hidden(true);
-								}
+
+								self.update_btn_visibility();
 							}
 						},
 						MouseButton::Middle => {
@@ -248,14 +439,32 @@ impl<'s> Scene<'s> {
 								self.dragging_start = (x, y);
 							}
 						},
+						MouseButton::Right => {
+							if let Some((row, col)) = self.clicked_cell(x, y) {
+								let cell = (row as u32, col as u32);
+								self.selection_start = Some(cell);
+								self.set_selection(Some(BoundingBox { top: cell.0, left: cell.1, bottom: cell.0, right: cell.1 }));
+							}
+						},
 						_ => {}
 					}
 				},
+				Event::DropFile { filename, .. } if self.state != State::ITERATING => self.load_pattern_file(filename.as_str()),
 				Event::MouseMotion { x, y, mousestate, ..} => {
+					if let Some(start) = self.selection_start {
+						if let (Some((row, col)), true) = (self.clicked_cell(x, y), mousestate.is_mouse_button_pressed(MouseButton::Right)) {
+							let cell = (row as u32, col as u32);
+							self.set_selection(Some(BoundingBox { top: start.0.min(cell.0), left: start.1.min(cell.1), bottom: start.0.max(cell.0), right: start.1.max(cell.1) }));
+						}
+					}
+					if self.dragging_minimap && mousestate.is_mouse_button_pressed(MouseButton::Left) {
+						let (row, col) = self.minimap.get_cell(x, y);
+						self.center_view_on(row, col);
+					}
 					if self.state != State::ITERATING && mousestate.is_mouse_button_pressed(MouseButton::Left) && !self.dragging {
-						let (indexes, clicked_canvas) = get_click_indexes(x, y);
-						if clicked_canvas && self.active_tool != Tool::HAND {
-							self.generation[(self.top_left_row as i32 + indexes.0) as usize][(self.top_left_col as i32 + indexes.1)  as usize] = self.active_tool == Tool::PENCIL;
+						if let (Some((row, col)), true) = (self.clicked_cell(x, y), self.active_tool != Tool::HAND) {
+							let state = if self.active_tool == Tool::PENCIL { self.paint_state } else { 0 };
+							self.set_cell(row, col, state);
 						}
 					}
 					if self.dragging {
@@ -311,79 +520,82 @@ impl<'s> Scene<'s> {
 	pub fn load_start_generation(&mut self){
 		
 		// GLIDER GUN START
-		self.generation[44][48] = true;
-
-		self.generation[45][46] = true;
-		self.generation[45][48] = true;
-
-		self.generation[46][36] = true;
-		self.generation[46][37] = true;
-		self.generation[46][44] = true;
-		self.generation[46][45] = true;
-		self.generation[46][58] = true;
-		self.generation[46][59] = true;
-
-		self.generation[47][35] = true;
-		self.generation[47][39] = true;
-		self.generation[47][44] = true;
-		self.generation[47][45] = true;
-		self.generation[47][58] = true;
-		self.generation[47][59] = true;
+		self.engine.set(44, 48, true);
+
+		self.engine.set(45, 46, true);
+		self.engine.set(45, 48, true);
+
+		self.engine.set(46, 36, true);
+		self.engine.set(46, 37, true);
+		self.engine.set(46, 44, true);
+		self.engine.set(46, 45, true);
+		self.engine.set(46, 58, true);
+		self.engine.set(46, 59, true);
+
+		self.engine.set(47, 35, true);
+		self.engine.set(47, 39, true);
+		self.engine.set(47, 44, true);
+		self.engine.set(47, 45, true);
+		self.engine.set(47, 58, true);
+		self.engine.set(47, 59, true);
 		
-		self.generation[48][24] = true;
-		self.generation[48][25] = true;
-		self.generation[48][34] = true;
-		self.generation[48][40] = true;
-		self.generation[48][44] = true;
-		self.generation[48][45] = true;
-
-		self.generation[49][34] = true;
-		self.generation[49][38] = true;
-		self.generation[49][40] = true;
-		self.generation[49][41] = true;
-		self.generation[49][46] = true;
-		self.generation[49][48] = true;
-		self.generation[49][24] = true;
-		self.generation[49][25] = true;
+		self.engine.set(48, 24, true);
+		self.engine.set(48, 25, true);
+		self.engine.set(48, 34, true);
+		self.engine.set(48, 40, true);
+		self.engine.set(48, 44, true);
+		self.engine.set(48, 45, true);
+
+		self.engine.set(49, 34, true);
+		self.engine.set(49, 38, true);
+		self.engine.set(49, 40, true);
+		self.engine.set(49, 41, true);
+		self.engine.set(49, 46, true);
+		self.engine.set(49, 48, true);
+		self.engine.set(49, 24, true);
+		self.engine.set(49, 25, true);
 		
-		self.generation[50][34] = true;
-		self.generation[50][40] = true;
-		self.generation[50][48] = true;
+		self.engine.set(50, 34, true);
+		self.engine.set(50, 40, true);
+		self.engine.set(50, 48, true);
 		
-		self.generation[51][35] = true;
-		self.generation[51][39] = true;
+		self.engine.set(51, 35, true);
+		self.engine.set(51, 39, true);
 		
-		self.generation[52][36] = true;
-		self.generation[52][37] = true;
+		self.engine.set(52, 36, true);
+		self.engine.set(52, 37, true);
 		// GLIDER GUN END
 
 		// LWSS START
-		self.generation[67][51] = true;
-		self.generation[67][52] = true;
+		self.engine.set(67, 51, true);
+		self.engine.set(67, 52, true);
 		
-		self.generation[68][50] = true;
-		self.generation[68][51] = true;
-		self.generation[68][53] = true;
-		self.generation[68][54] = true;
+		self.engine.set(68, 50, true);
+		self.engine.set(68, 51, true);
+		self.engine.set(68, 53, true);
+		self.engine.set(68, 54, true);
 		
-		self.generation[69][51] = true;
-		self.generation[69][52] = true;
-		self.generation[69][53] = true;
-		self.generation[69][54] = true;
+		self.engine.set(69, 51, true);
+		self.engine.set(69, 52, true);
+		self.engine.set(69, 53, true);
+		self.engine.set(69, 54, true);
 		
-		self.generation[70][52] = true;
-		self.generation[70][53] = true;
+		self.engine.set(70, 52, true);
+		self.engine.set(70, 53, true);
 		// LWSS END
 	}
 
 	pub fn iteration(&mut self) {
+		let frame_start = Instant::now();
+		let mut step_duration = Duration::ZERO;
+
 		// Clear the window
 		self.canvas.set_draw_color(Color::BLACK);
 		self.canvas.clear();
 		
 		// Draw population & information text
 		{
-			let surface = self.main_font.as_ref().unwrap().render(format!("Generation: {} :: Population: {}", self.generation_number, self.population_amount).as_str())
+			let surface = self.main_font.as_ref().unwrap().render(format!("Generation: {} :: Population: {}", self.engine.generation_number(), self.engine.population()).as_str())
 				.blended(Color::WHITE).unwrap();
 
 			let surface_2 = self.main_font.as_ref().unwrap().render(format!("[row:{}, col:{}]", self.top_left_row, self.top_left_col).as_str())
@@ -402,28 +614,70 @@ impl<'s> Scene<'s> {
 			let _ = self.canvas.copy(&texture_2, None, Some(Rect::new((crate::H_MARGIN+crate::GRID_WIDTH - width) as i32, (crate::V_MARGIN - height - 5) as i32  , width, height)));
 		}
 		
-		// Update population amount
-		self.population_amount = self.draw_current_generation();
+		// Update visible population amount. Cells of shifted hexagonal rows stick out of the grid
+		self.canvas.set_clip_rect(Rect::new(crate::H_MARGIN as i32, crate::V_MARGIN as i32, crate::GRID_WIDTH, crate::GRID_HEIGHT));
+		self.visible_population = self.draw_current_generation();
+
+		// Tint the regions that have their own rule
+		if !self.engine.rule_map().is_empty() {
+			self.draw_regions();
+		}
+
+		// Draw the accumulated activity heatmap
+		if self.show_heatmap {
+			self.draw_heatmap();
+		}
+
+		// Draw births/deaths & neighbor count overlays
+		if self.show_changes_overlay {
+			self.draw_changes_overlay();
+		}
+		if self.show_neighbor_counts && crate::SIZE >= NEIGHBOR_COUNT_MIN_SIZE && self.engine.rule().range.is_none() && self.engine.rule().block.is_none() { // Larger than Life counts don't fit in a cell, and block rules don't count
+			self.draw_neighbor_counts();
+		}
+		self.canvas.set_clip_rect(None);
 
 		// Iterate the generation and count
 		if self.state == State::ITERATING && self.last_iteration.elapsed() > crate::ITERATION_COOLDOWN {
-			(self.generation, self.change_matrix) = self.iterate_generation(&self.generation);
-			self.generation_number += 1;
-			self.last_iteration = std::time::Instant::now();
+			self.step_generation();
+			step_duration = self.last_iteration_stats.as_ref().map_or(Duration::ZERO, |stats| stats.duration);
 		}
 
 		// Draw UI
 		self.draw_lines();
+		self.draw_selection();
+		if self.show_debug_overlay {
+			self.draw_debug_overlay();
+		}
+		if self.census.is_some() {
+			self.draw_census();
+		}
 		self.draw_buttons();
+		self.draw_side_panel();
+		if let Some(editor) = self.rule_editor.as_mut() {
+			editor.draw(&mut self.canvas, self.main_font.as_ref().unwrap());
+		}
 
 		self.canvas.present();
+
+		// Update render timings
+		self.render_duration = frame_start.elapsed().saturating_sub(step_duration);
+		self.frame_count += 1;
+		if self.fps_timer.elapsed() >= Duration::from_secs(1) {
+			self.fps = self.frame_count;
+			self.frame_count = 0;
+			self.fps_timer = Instant::now();
+		}
 	}
 
 	fn set_state(&mut self, new_state: State) {
 		self.state = new_state;
+		self.update_btn_visibility();
+	}
 
-		if new_state == State::IDLE {
-			if self.generation_number == 0 {
+	fn update_btn_visibility(&mut self) {
+		if self.state != State::ITERATING {
+			if self.engine.generation_number() == 0 {
 				self.btn_start_simulation.set_hidden(false);
 				self.btn_pause_resume_simulation.set_hidden(true);
 				self.btn_abort_simulation.set_hidden(true);
@@ -435,11 +689,13 @@ impl<'s> Scene<'s> {
 				self.btn_abort_n_save_simulation.set_hidden(false);
 			}
 			self.btn_clear_generation.set_hidden(false);
+			self.btn_step_simulation.set_hidden(false);
 			self.btn_tool_pencil.set_hidden(false);
 			self.btn_tool_eraser.set_hidden(false);
 		} else {
 			self.btn_start_simulation.set_hidden(true);
 			self.btn_clear_generation.set_hidden(true);
+			self.btn_step_simulation.set_hidden(true);
 			self.btn_pause_resume_simulation.set_hidden(false);
 			self.btn_abort_simulation.set_hidden(false);
 			self.btn_abort_n_save_simulation.set_hidden(false);
@@ -448,6 +704,233 @@ impl<'s> Scene<'s> {
 		}
 	}
 
+	fn set_cell(&mut self, row: usize, col: usize, state: u8) {
+		self.engine.set_state(row, col, state);
+		self.minimap.set_cell(self.engine.generation(), row, col);
+		self.classifier.reset(self.selection); // The edited pattern has no history
+
+		// Manual edits are not births or deaths, keep them out of the changes overlay
+		if let Some(last_step_generation) = self.last_step_generation.as_mut() {
+			last_step_generation[row][col] = self.engine.get(row, col);
+		}
+	}
+
+	fn step_generation(&mut self) {
+		let previous_population = self.engine.population();
+		let (old_generation, stats) = self.engine.iterate();
+		self.heatmap.record(&old_generation, self.engine.generation(), self.engine.change_matrix());
+		self.minimap.refresh(self.engine.generation(), self.engine.change_matrix());
+		self.push_statistics(stats.births, stats.deaths);
+		let classification = self.classifier.observe(&self.engine);
+
+		if self.state == State::ITERATING && self.auto_stop {
+			if let Some(stop_reason) = self.stop_conditions.check(&self.engine, classification, previous_population) {
+				println!("[INFO] Simulation stopped at generation {}: {}", self.engine.generation_number(), stop_reason);
+				self.stop_reason = Some(stop_reason);
+				self.set_state(State::PAUSE);
+				self.btn_pause_resume_simulation.set_text("Resume".to_string());
+				self.set_tool(Tool::PENCIL);
+				self.update_btn_visibility();
+			}
+		}
+
+		self.last_step_generation = Some(old_generation);
+		self.last_iteration_stats = Some(stats);
+		self.last_iteration = std::time::Instant::now();
+	}
+
+	// Saves the starting generation and resets everything accumulated during the previous run
+	fn start_run(&mut self) {
+		self.previous_generation = self.engine.generation().clone();
+		self.previous_states = self.engine.states().clone();
+		self.heatmap.reset();
+		self.statistics.clear();
+		self.push_statistics(0, 0);
+		self.classifier.reset(self.selection);
+		self.classifier.observe(&self.engine);
+		self.stop_reason = None;
+		self.notice = None;
+	}
+
+	// Replaces the whole generation with an RLE pattern placed in the middle of the view, and switches to its rule. A .rule file
+	// only switches the rule, keeping the cells
+	pub fn load_pattern_file(&mut self, path: &str) {
+		if path.ends_with(".rule") {
+			self.load_rule_file(path);
+			return;
+		}
+		let (pattern, rule) = match rle::load(path).and_then(|pattern| pattern.parsed_rule().map(|rule| (pattern, rule))) {
+			Ok(loaded) => loaded,
+			Err(error) => {
+				println!("[ERROR] Could not load the pattern: {}", error);
+				self.notice = Some("Could not load the pattern".to_string());
+				return;
+			}
+		};
+
+		let top = (self.top_left_row + crate::VIEW_ROWS / 2).saturating_sub(pattern.height / 2).min(crate::MATRIX_SIZE.saturating_sub(pattern.height));
+		let left = (self.top_left_col + crate::VIEW_COLS / 2).saturating_sub(pattern.width / 2).min(crate::MATRIX_SIZE.saturating_sub(pattern.width));
+		self.engine.clear();
+		self.engine.reset();
+		self.engine.set_rule(rule);
+		if self.paint_state >= self.engine.rule().states {
+			self.paint_state = 1;
+		}
+		if let Err(error) = self.engine.load_pattern(&pattern, top, left) {
+			println!("[ERROR] Could not load the pattern: {}", error);
+			self.notice = Some("The pattern is too big".to_string());
+		} else {
+			println!("[INFO] Loaded the {}x{} pattern {} ({})", pattern.width, pattern.height, path, self.engine.rule());
+			self.notice = Some(format!("Loaded a {}x{} pattern", pattern.width, pattern.height));
+		}
+
+		self.minimap.refresh_all(self.engine.generation());
+		self.last_step_generation = None;
+		self.heatmap.reset();
+		self.statistics.clear();
+		self.classifier.reset(self.selection);
+		self.census = None;
+		self.stop_reason = None;
+		self.set_state(State::IDLE);
+	}
+
+	fn load_rule_file(&mut self, path: &str) {
+		match rule_table::load(path) {
+			Ok(rule) => {
+				println!("[INFO] Loaded the rule {} ({} states)", rule, rule.states);
+				self.switch_rule(rule);
+			},
+			Err(error) => {
+				println!("[ERROR] Could not load the rule: {}", error);
+				self.notice = Some("Could not load the rule".to_string());
+			}
+		}
+	}
+
+	// The cells are kept, the simulation starts over from them
+	fn switch_rule(&mut self, rule: Rule) {
+		self.notice = Some(format!("Switched to {}", rule));
+		self.engine.set_rule(rule);
+		self.engine.reset();
+		if self.paint_state >= self.engine.rule().states {
+			self.paint_state = 1;
+		}
+		self.minimap.refresh_all(self.engine.generation());
+		self.last_step_generation = None;
+		self.set_state(State::IDLE);
+	}
+
+	// The rest of the universe keeps its rule
+	fn set_region_rule(&mut self, rule: Rule) {
+		let selection = match self.selection {
+			Some(selection) => selection,
+			None => return
+		};
+		let name = rule.to_string();
+		match self.engine.set_region_rule(selection, rule) {
+			Ok(()) => {
+				println!("[INFO] Region set to {}", name);
+				self.notice = Some(format!("Region set to {}", name));
+			},
+			Err(error) => {
+				println!("[ERROR] Could not set the region rule: {}", error);
+				self.notice = Some("Rules can't be mixed".to_string());
+			}
+		}
+	}
+
+	fn find_predecessor(&mut self) {
+		let selection = match self.selection {
+			Some(selection) => selection,
+			None => {
+				self.notice = Some("Select a region first".to_string());
+				return;
+			}
+		};
+		if selection.top == 0 || selection.left == 0 || selection.bottom == crate::MATRIX_SIZE - 1 || selection.right == crate::MATRIX_SIZE - 1 {
+			self.notice = Some("The selection touches the edge".to_string());
+			return;
+		}
+
+		if self.engine.rule().states > 2 || self.engine.rule().range.is_some() || self.engine.rule().table.is_some() || self.engine.rule().block.is_some() || !self.engine.region_rules().is_empty() {
+			self.notice = Some("Not available in this rule".to_string());
+			return;
+		}
+
+		let cells: Vec<(u32, u32)> = self.engine.alive_cells(Some(selection)).iter().map(|(row, col)| (row - selection.top, col - selection.left)).collect();
+		match predecessor::find(selection.width(), selection.height(), &cells, self.engine.rule()) {
+			Ok(Some(parent)) => {
+				for row in selection.top - 1..=selection.bottom + 1 {
+					for col in selection.left - 1..=selection.right + 1 {
+						self.set_cell(row as usize, col as usize, 0);
+					}
+				}
+				for (row, col) in parent.cells.iter() {
+					self.set_cell((selection.top - 1 + row) as usize, (selection.left - 1 + col) as usize, 1);
+				}
+				println!("[INFO] Predecessor of the {}x{} selection found", selection.width(), selection.height());
+				self.notice = Some("Predecessor found, step to check".to_string());
+			},
+			Ok(None) => {
+				println!("[INFO] The {}x{} selection has no predecessor, it's a Garden of Eden", selection.width(), selection.height());
+				self.notice = Some("Garden of Eden: no predecessor".to_string());
+			},
+			Err(error) => {
+				println!("[ERROR] Could not search a predecessor: {}", error);
+				self.notice = Some(format!("Selection too big (max {})", predecessor::MAX_SIDE));
+			}
+		}
+	}
+
+	// Splits the selection (or the whole pattern) into objects and identifies them, or hides the previous census
+	fn toggle_census(&mut self) {
+		if self.census.take().is_some() {
+			return;
+		}
+
+		self.census = Some(Census::take(&self.engine, self.selection));
+		self.census_generation = self.engine.generation_number();
+	}
+
+	// The selection limits the cells observed by the classifier
+	fn set_selection(&mut self, selection: Option<BoundingBox>) {
+		self.selection = selection;
+		self.classifier.reset(selection);
+	}
+
+	fn push_statistics(&mut self, births: u64, deaths: u64) {
+		self.statistics.push(Record {
+			generation: self.engine.generation_number(),
+			population: self.engine.population(),
+			births,
+			deaths,
+			bounding_box: self.engine.bounding_box(),
+			live_chunks: self.engine.live_chunks()
+		});
+	}
+
+	// Advances a single generation from the Idle or Pause states, leaving the simulation paused
+	fn step_simulation(&mut self) {
+		if self.state == State::IDLE {
+			self.start_run();
+		}
+		self.step_generation();
+		self.set_state(State::PAUSE);
+		self.btn_pause_resume_simulation.set_text("Resume".to_string());
+		self.set_tool(Tool::PENCIL);
+	}
+
+	// Moves the viewport so the given matrix cell is at its center, keeping it inside the matrix
+	fn center_view_on(&mut self, row: u32, col: u32) {
+		self.top_left_row = row.saturating_sub(crate::VIEW_ROWS / 2).min(crate::MATRIX_SIZE - crate::VIEW_ROWS);
+		self.top_left_col = col.saturating_sub(crate::VIEW_COLS / 2 + self.view_shear()).min(crate::MATRIX_SIZE - crate::VIEW_COLS);
+	}
+
+	// Matrix cell in the middle of the view
+	fn view_center(&self) -> (u32, u32) {
+		(self.top_left_row + crate::VIEW_ROWS / 2, self.top_left_col + crate::VIEW_COLS / 2 + self.view_shear())
+	}
+
 	fn set_tool(&mut self, new_tool: Tool) {
 		self.btn_tool_pencil.set_active(Tool::PENCIL == new_tool);
 		self.btn_tool_eraser.set_active(Tool::ERASER == new_tool);
@@ -463,6 +946,7 @@ impl<'s> Scene<'s> {
 		self.btn_abort_simulation.update_hover(x, y);
 		self.btn_abort_n_save_simulation.update_hover(x, y);
 		self.btn_clear_generation.update_hover(x, y);
+		self.btn_step_simulation.update_hover(x, y);
 
 		// Icon buttons
 		self.btn_tool_pencil.update_hover(x, y);
@@ -470,211 +954,410 @@ impl<'s> Scene<'s> {
 		self.btn_tool_hand.update_hover(x, y);
 	}
 
+	fn hexagonal(&self) -> bool {
+		self.engine.rule().neighborhood == Neighborhood::Hexagonal
+	}
+
+	// Columns the middle row of the view is shifted right in the matrix, the sheared hexagonal view moves one every two rows
+	fn view_shear(&self) -> u32 {
+		if self.hexagonal() { crate::VIEW_ROWS / 4 } else { 0 }
+	}
+
+	// Matrix cell shown at a position of the view, and its rectangle on screen. Hexagonal rules are drawn as offset rows, each
+	// row shifted half a cell left of the one above so every cell touches its 6 neighbors: the view is sheared over the matrix
+	fn view_cell(&self, row: u32, col: u32) -> Option<(usize, usize, Rect)> {
+		let (shift, offset) = if self.hexagonal() { (row / 2, (row % 2 * crate::SIZE / 2) as i32) } else { (0, 0) };
+		let (matrix_row, matrix_col) = (self.top_left_row + row, self.top_left_col + col + shift);
+		if row >= crate::VIEW_ROWS || matrix_col >= crate::MATRIX_SIZE {
+			return None;
+		}
+		Some((matrix_row as usize, matrix_col as usize, Rect::new((crate::H_MARGIN + col * crate::SIZE) as i32 - offset, (crate::V_MARGIN + row * crate::SIZE) as i32, crate::SIZE, crate::SIZE)))
+	}
+
+	// Every visible cell. Shifted rows show part of one more cell, clipped to the grid
+	fn view_cells(&self) -> Vec<(usize, usize, Rect)> {
+		let cols = if self.hexagonal() { crate::VIEW_COLS + 1 } else { crate::VIEW_COLS };
+		(0..crate::VIEW_ROWS).flat_map(|row| (0..cols).filter_map(move |col| self.view_cell(row, col))).collect()
+	}
+
+	// Matrix cell under a point of the screen, if it's on the grid
+	fn clicked_cell(&self, x: i32, y: i32) -> Option<(usize, usize)> {
+		let ((row, _), clicked_canvas) = get_click_indexes(x, y);
+		if !clicked_canvas {
+			return None;
+		}
+		let offset = if self.hexagonal() { (row as u32 % 2 * crate::SIZE / 2) as i32 } else { 0 };
+		let col = (x - crate::H_MARGIN as i32 + offset) / crate::SIZE as i32;
+		self.view_cell(row as u32, col as u32).map(|(row, col, _)| (row, col))
+	}
+
 	fn draw_current_generation(&mut self) -> u32 {
 		let mut population = 0;
-		for row in 0..crate::VIEW_ROWS {
-			for col in 0..crate::VIEW_COLS {
-				if self.generation[(self.top_left_row + row) as usize][(self.top_left_col + col) as usize] == true {
-					self.canvas.set_draw_color(crate::COLOR_WHITE);
-					let drawing_rect = Rect::new((crate::H_MARGIN + col * crate::SIZE) as i32, (crate::V_MARGIN + row * crate::SIZE) as i32, crate::SIZE, crate::SIZE);
-					let _ = self.canvas.fill_rect(drawing_rect);
+		for (row, col, drawing_rect) in self.view_cells() {
+			let state = self.engine.get_state(row, col);
+			if state != 0 {
+				self.canvas.set_draw_color(crate::state_color(state, self.engine.rule()));
+				let _ = self.canvas.fill_rect(drawing_rect);
+				if self.engine.rule().alive(state) && drawing_rect.right() <= (crate::H_MARGIN + crate::GRID_WIDTH) as i32 {
 					population += 1;
-				} else {
-					if (((self.top_left_row+row)/crate::GRID_BIG_CELL_SIZE)%2 == 0 && ((self.top_left_col+col)/crate::GRID_BIG_CELL_SIZE)%2 == 0) || (((self.top_left_row+row)/crate::GRID_BIG_CELL_SIZE)%2 != 0 && ((self.top_left_col+col)/crate::GRID_BIG_CELL_SIZE)%2 != 0 ) {
-						self.canvas.set_draw_color(crate::COLOR_BLACK_1);
-						let drawing_rect = Rect::new((crate::H_MARGIN + col * crate::SIZE) as i32, (crate::V_MARGIN + row * crate::SIZE) as i32, crate::SIZE, crate::SIZE);
-						let _ = self.canvas.fill_rect(drawing_rect);
-					}
 				}
+			} else if (row as u32 / crate::GRID_BIG_CELL_SIZE) % 2 == (col as u32 / crate::GRID_BIG_CELL_SIZE) % 2 {
+				self.canvas.set_draw_color(crate::COLOR_BLACK_1);
+				let _ = self.canvas.fill_rect(drawing_rect);
 			}
 		}
 		return population;
 	}
 
-	fn draw_lines(&mut self) {
-		// Main grid lines
-		self.canvas.set_draw_color(crate::COLOR_BLACK_2);
-		for row in 1..crate::VIEW_COLS {
-			let start_point = Point::new((crate::H_MARGIN + crate::SIZE * row) as i32, crate::V_MARGIN as i32);
-			let end_point = Point::new((crate::H_MARGIN + crate::SIZE * row) as i32, (crate::V_MARGIN + (crate::VIEW_ROWS * crate::SIZE)-1) as i32);
-			let _ = self.canvas.draw_line(start_point, end_point);
+	fn draw_heatmap(&mut self) {
+		self.canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+		for (row, col, drawing_rect) in self.view_cells() {
+			let heat = self.heatmap.get(row as u32, col as u32);
+			if heat == 0 {
+				continue;
+			}
+
+			let color = self.heatmap.color(heat);
+			self.canvas.set_draw_color(Color::RGBA(color.r, color.g, color.b, HEATMAP_ALPHA));
+			let _ = self.canvas.fill_rect(drawing_rect);
 		}
-		for row in 1..crate::VIEW_ROWS {
-			let start_point = Point::new(crate::H_MARGIN as i32, (crate::V_MARGIN + crate::SIZE * row) as i32);
-			let end_point = Point::new((crate::H_MARGIN + (crate::VIEW_COLS * crate::SIZE)-1) as i32, (crate::V_MARGIN + crate::SIZE * row) as i32);
-			let _ = self.canvas.draw_line(start_point, end_point);
+		self.canvas.set_blend_mode(sdl2::render::BlendMode::None);
+	}
+
+	fn draw_regions(&mut self) {
+		self.canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+		for (row, col, drawing_rect) in self.view_cells() {
+			let index = self.engine.rule_map()[row / SUB_MATRIX_CHUNK_SIZE as usize][col / SUB_MATRIX_CHUNK_SIZE as usize];
+			if index == 0 {
+				continue;
+			}
+
+			let color = REGION_COLORS[(index as usize - 1) % REGION_COLORS.len()];
+			self.canvas.set_draw_color(Color::RGBA(color.r, color.g, color.b, REGION_ALPHA));
+			let _ = self.canvas.fill_rect(drawing_rect);
 		}
-		
-		// Outer grid lines
-		self.canvas.set_draw_color(crate::COLOR_BLACK_3);
-		let _ = self.canvas.draw_rect(Rect::new(crate::H_MARGIN as i32, crate::V_MARGIN as i32, crate::VIEW_COLS * crate::SIZE, crate::VIEW_ROWS * crate::SIZE));
+		self.canvas.set_blend_mode(sdl2::render::BlendMode::None);
 	}
 
-	fn draw_buttons(&mut self) {
-		// Text buttons
-		self.btn_start_simulation.draw(&mut self.canvas, self.main_font.as_ref().unwrap());
-		self.btn_pause_resume_simulation.draw(&mut self.canvas, self.main_font.as_ref().unwrap());
-		self.btn_clear_generation.draw(&mut self.canvas, self.main_font.as_ref().unwrap());
-		self.btn_abort_simulation.draw(&mut self.canvas, self.main_font.as_ref().unwrap());
-		self.btn_abort_n_save_simulation.draw(&mut self.canvas, self.main_font.as_ref().unwrap());
-		
-		// Icon buttons
-		self.btn_tool_pencil.draw(&mut self.canvas);
-		self.btn_tool_eraser.draw(&mut self.canvas);
-		self.btn_tool_hand.draw(&mut self.canvas);
+	fn draw_changes_overlay(&mut self) {
+		let last_step_generation = match self.last_step_generation.as_ref() {
+			Some(last_step_generation) => last_step_generation,
+			None => return
+		};
+
+		self.canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+		for (matrix_row, matrix_col, drawing_rect) in self.view_cells() {
+			let alive = self.engine.get(matrix_row, matrix_col);
+			let was_alive = last_step_generation[matrix_row][matrix_col];
+
+			let color = if alive && !was_alive { // Born this generation
+				crate::COLOR_GREEN
+			} else if !alive && was_alive { // Died this generation
+				Color::RGBA(crate::COLOR_RED.r, crate::COLOR_RED.g, crate::COLOR_RED.b, OVERLAY_ALPHA)
+			} else {
+				continue;
+			};
+
+			self.canvas.set_draw_color(color);
+			let _ = self.canvas.fill_rect(drawing_rect);
+		}
+		self.canvas.set_blend_mode(sdl2::render::BlendMode::None);
 	}
-	
 
+	fn draw_neighbor_counts(&mut self) {
+		// Render each digit once per frame and reuse it for every cell
+		let digit_textures: Vec<sdl2::render::Texture> = (0..=8).map(|digit| {
+			let surface = self.small_font.as_ref().unwrap().render(digit.to_string().as_str())
+				.blended(crate::COLOR_BLUE).unwrap();
+			self.texture_creator.create_texture_from_surface(&surface).unwrap()
+		}).collect();
+
+		for (row, col, cell_rect) in self.view_cells() {
+			let neighbors = self.engine.get_neighbors(row as i32, col as i32);
+			if neighbors == 0 {
+				continue;
+			}
 
-	fn get_neighbors(&self, target_i : i32, target_j : i32) -> i8 {
-		let mut neighbors  = 0 as i8;
+			let texture = &digit_textures[neighbors as usize];
+			let TextureQuery { width, height, .. } = texture.query();
+			let _ = self.canvas.copy(texture, None, Some(Rect::new(cell_rect.x() + (crate::SIZE as i32 - width as i32) / 2, cell_rect.y() + (crate::SIZE as i32 - height as i32) / 2, width, height)));
+		}
+	}
 
-		let search_i_from = i32::max(0, target_i - 1);
-		let search_i_to = i32::min(crate::MATRIX_SIZE as i32, target_i + 2);
-		let search_j_from = i32::max(0, target_j - 1);
-		let search_j_to = i32::min(crate::MATRIX_SIZE as i32, target_j + 2);
+	fn draw_text(&mut self, text: &str, x: i32, y: i32, color: Color) -> (u32, u32) {
+		let surface = self.main_font.as_ref().unwrap().render(text)
+			.blended(color).unwrap();
+		let texture = self.texture_creator.create_texture_from_surface(&surface).unwrap();
 
-		for row in search_i_from..search_i_to {
-			for col in search_j_from..search_j_to {
-				if row != target_i || col != target_j {
-					if self.generation[row as usize][col as usize] == true {
-						neighbors += 1;
-					}
+		let TextureQuery { width, height, .. } = texture.query();
+		let _ = self.canvas.copy(&texture, None, Some(Rect::new(x, y, width, height)));
+		(width, height)
+	}
+
+	fn draw_debug_overlay(&mut self) {
+		// Tint every visible chunk depending on how it was evaluated in the last iteration. The hexagonal view is sheared over the
+		// chunks, so its cells are tinted one by one
+		if let (Some(stats), true) = (self.last_iteration_stats.as_ref(), self.hexagonal()) {
+			self.canvas.set_clip_rect(Rect::new(crate::H_MARGIN as i32, crate::V_MARGIN as i32, crate::GRID_WIDTH, crate::GRID_HEIGHT));
+			self.canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+			for (row, col, drawing_rect) in self.view_cells() {
+				let color = match stats.chunk_evaluations[row / SUB_MATRIX_CHUNK_SIZE as usize][col / SUB_MATRIX_CHUNK_SIZE as usize] {
+					ChunkEvaluation::Full => crate::COLOR_RED,
+					ChunkEvaluation::Border => crate::COLOR_YELLOW,
+					ChunkEvaluation::Skipped => crate::COLOR_BLUE
+				};
+				self.canvas.set_draw_color(Color::RGBA(color.r, color.g, color.b, DEBUG_ALPHA));
+				let _ = self.canvas.fill_rect(drawing_rect);
+			}
+			self.canvas.set_blend_mode(sdl2::render::BlendMode::None);
+			self.canvas.set_clip_rect(None);
+		} else if let Some(stats) = self.last_iteration_stats.as_ref() {
+			self.canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+			let first_sub_row = self.top_left_row / SUB_MATRIX_CHUNK_SIZE;
+			let last_sub_row = (self.top_left_row + crate::VIEW_ROWS - 1) / SUB_MATRIX_CHUNK_SIZE;
+			let first_sub_col = self.top_left_col / SUB_MATRIX_CHUNK_SIZE;
+			let last_sub_col = (self.top_left_col + crate::VIEW_COLS - 1) / SUB_MATRIX_CHUNK_SIZE;
+
+			for sub_row in first_sub_row..=last_sub_row {
+				for sub_col in first_sub_col..=last_sub_col {
+					let color = match stats.chunk_evaluations[sub_row as usize][sub_col as usize] {
+						ChunkEvaluation::Full => crate::COLOR_RED,
+						ChunkEvaluation::Border => crate::COLOR_YELLOW,
+						ChunkEvaluation::Skipped => crate::COLOR_BLUE
+					};
+
+					// Clip the chunk to the viewport
+					let from_row = u32::max(sub_row * SUB_MATRIX_CHUNK_SIZE, self.top_left_row) - self.top_left_row;
+					let to_row = u32::min((sub_row + 1) * SUB_MATRIX_CHUNK_SIZE, self.top_left_row + crate::VIEW_ROWS) - self.top_left_row;
+					let from_col = u32::max(sub_col * SUB_MATRIX_CHUNK_SIZE, self.top_left_col) - self.top_left_col;
+					let to_col = u32::min((sub_col + 1) * SUB_MATRIX_CHUNK_SIZE, self.top_left_col + crate::VIEW_COLS) - self.top_left_col;
+					let chunk_rect = Rect::new((crate::H_MARGIN + from_col * crate::SIZE) as i32, (crate::V_MARGIN + from_row * crate::SIZE) as i32, (to_col - from_col) * crate::SIZE, (to_row - from_row) * crate::SIZE);
+
+					self.canvas.set_draw_color(Color::RGBA(color.r, color.g, color.b, DEBUG_ALPHA));
+					let _ = self.canvas.fill_rect(chunk_rect);
+					self.canvas.set_draw_color(color);
+					let _ = self.canvas.draw_rect(chunk_rect);
 				}
 			}
+			self.canvas.set_blend_mode(sdl2::render::BlendMode::None);
 		}
-		
-		return neighbors;
-	}
-
-	fn iterate_generation(&self, generation : &Vec<Vec<bool>>) -> (Vec<Vec<bool>>, Vec<Vec<bool>>) {
-		let it_start = Instant::now();
-		let mut new_generation : Vec<Vec<bool>> = generation.clone();
-		let mut new_change_matrix : Vec<Vec<bool>> = self.change_matrix.clone();
-
-		for sub_row in 0..SUB_MATRIX_SIZE {
-			for sub_col in 0..SUB_MATRIX_SIZE {
-
-				let previously_changed = self.change_matrix[sub_row as usize][sub_col as usize];
-				let has_changed_neighbors = self.has_changed_neighbors(sub_row, sub_col);
-				let iterate_sub_matrix = previously_changed || has_changed_neighbors;
-				
-				if iterate_sub_matrix { // Matrix has changed in last iteration
-					let mut has_changed = false;
-
-					if !previously_changed && has_changed_neighbors { // The cell had no changes, but need to check borders in case neighbor cells are moving towards this cell. This reduces iterations on aprox 75%
-						
-						let mut elems_top: Vec<(usize, usize)> = (0..SUB_MATRIX_CHUNK_SIZE).map(|i| ((sub_row*SUB_MATRIX_CHUNK_SIZE) as usize, (sub_col*SUB_MATRIX_CHUNK_SIZE+i) as usize)).collect(); // Top border
-						let mut elems_bottom: Vec<(usize, usize)> = (0..SUB_MATRIX_CHUNK_SIZE).map(|i| (((sub_row+1)*SUB_MATRIX_CHUNK_SIZE-1) as usize, (sub_col*SUB_MATRIX_CHUNK_SIZE+i) as usize)).collect(); // Bottom border
-						let mut elems_left: Vec<(usize, usize)> = (0..SUB_MATRIX_CHUNK_SIZE).map(|i| ((sub_row*SUB_MATRIX_CHUNK_SIZE+i) as usize, (sub_col*SUB_MATRIX_CHUNK_SIZE) as usize)).collect(); // Left border
-						let mut elems_right: Vec<(usize, usize)> = (0..SUB_MATRIX_CHUNK_SIZE).map(|i| ((sub_row*SUB_MATRIX_CHUNK_SIZE+i) as usize, ((sub_col+1)*SUB_MATRIX_CHUNK_SIZE-1) as usize)).collect(); // Left border
-	
 
-						let mut all_elems: Vec<(usize, usize)> = Vec::new();
-						all_elems.append(&mut elems_top);
-						all_elems.append(&mut elems_bottom);
-						all_elems.append(&mut elems_left);
-						all_elems.append(&mut elems_right);
-
-						for elem in all_elems {
-							let row = elem.0;
-							let col = elem.1;
-							let neighbors = self.get_neighbors(row as i32, col as i32);
-							if generation[row][col] == true { // Alive
-								if !(neighbors == 2 || neighbors == 3) {
-									new_generation[row][col] = false;
-									has_changed = true;
-								}
-							} else { // Dead
-								if neighbors == 3 {
-									new_generation[row][col] = true;
-									has_changed = true;
-								}
-							}
-						}
+		// Information panel
+		let active_chunks = self.engine.change_matrix().iter().flatten().filter(|changed| **changed).count();
+		let total_chunks = crate::SUB_MATRIX_SIZE * crate::SUB_MATRIX_SIZE;
+		let mut lines = vec![
+			format!("FPS: {}", self.fps),
+			format!("Render: {:.2}ms", self.render_duration.as_secs_f32() * 1000.0),
+			format!("Active chunks: {}/{}", active_chunks, total_chunks)
+		];
+		if let Some(stats) = self.last_iteration_stats.as_ref() {
+			lines.push(format!("Step: {:.2}ms", stats.duration.as_secs_f32() * 1000.0));
+			lines.push(format!("Full chunks: {}", stats.full_chunks));
+			lines.push(format!("Border chunks: {}", stats.border_chunks));
+			lines.push(format!("Skipped chunks: {}", total_chunks - stats.full_chunks - stats.border_chunks));
+		}
 
+		self.draw_text_panel(&lines, crate::H_MARGIN as i32, false);
+	}
 
+	fn draw_census(&mut self) {
+		let mut lines = match self.census.as_ref() {
+			Some(census) if census.entries.is_empty() => vec!["Census: no objects".to_string()],
+			Some(census) => {
+				let total: u32 = census.entries.iter().map(|entry| entry.count).sum();
+				let mut lines = vec![format!("Census of generation {}: {} objects", self.census_generation, total)];
+				lines.extend(census.entries.iter().take(CENSUS_MAX_LINES).map(|entry| match entry.name {
+					Some(name) => format!("{:>5} {} ({})", entry.count, entry.apgcode, name),
+					None => format!("{:>5} {}", entry.count, entry.apgcode)
+				}));
+				lines
+			},
+			None => return
+		};
+		let hidden = self.census.as_ref().map_or(0, |census| census.entries.len().saturating_sub(CENSUS_MAX_LINES));
+		if hidden > 0 {
+			lines.push(format!("... and {} more", hidden));
+		}
 
-					} else { // The cell had changes
-						for row in sub_row*(SUB_MATRIX_CHUNK_SIZE)..(sub_row+1)*(SUB_MATRIX_CHUNK_SIZE) {
-							for col in sub_col*(SUB_MATRIX_CHUNK_SIZE)..(sub_col+1)*(SUB_MATRIX_CHUNK_SIZE) {
-								let neighbors = self.get_neighbors(row as i32, col as i32);
-								if generation[row as usize][col as usize] == true { // Alive
-									if !(neighbors == 2 || neighbors == 3) {
-										new_generation[row as usize][col as usize] = false;
-										has_changed = true;
-									}
-								} else { // Dead
-									if neighbors == 3 {
-										new_generation[row as usize][col as usize] = true;
-										has_changed = true;
-									}
-								}
-							}
-						}
-					}
+		self.draw_text_panel(&lines, (crate::H_MARGIN + crate::GRID_WIDTH) as i32, true);
+	}
 
+	// Draws the lines over a dark background at the top of the grid, starting or ending at the given x
+	fn draw_text_panel(&mut self, lines: &[String], x: i32, align_right: bool) {
+		let line_height = self.main_font.as_ref().unwrap().height() as u32;
+		let panel_width = lines.iter().map(|line| self.main_font.as_ref().unwrap().size_of(line).map_or(0, |(width, _)| width)).max().unwrap_or(0) + DEBUG_PANEL_PADDING * 2;
+		let panel_x = if align_right { x - panel_width as i32 } else { x };
+		let panel_rect = Rect::new(panel_x, crate::V_MARGIN as i32, panel_width, line_height * lines.len() as u32 + DEBUG_PANEL_PADDING * 2);
 
-					new_change_matrix[sub_row as usize][sub_col as usize] = has_changed;
-				}
+		self.canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+		self.canvas.set_draw_color(Color::RGBA(0, 0, 0, 200));
+		let _ = self.canvas.fill_rect(panel_rect);
+		self.canvas.set_blend_mode(sdl2::render::BlendMode::None);
+
+		for (index, line) in lines.iter().enumerate() {
+			self.draw_text(line, panel_rect.x + DEBUG_PANEL_PADDING as i32, panel_rect.y + (DEBUG_PANEL_PADDING + line_height * index as u32) as i32, crate::COLOR_WHITE);
+		}
+	}
+
+	fn draw_side_panel(&mut self) {
+		let panel_x = (crate::H_MARGIN * 2 + crate::GRID_WIDTH) as i32;
+		let line_height = self.main_font.as_ref().unwrap().height() as u32;
+		self.draw_text("Overview", panel_x, (crate::V_MARGIN - line_height - 5) as i32, crate::COLOR_WHITE);
+
+		let (view_center_row, view_center_col) = self.view_center();
+		self.minimap.draw(&mut self.canvas, view_center_row, view_center_col);
+
+		// Automatic stop
+		let mut y = (crate::V_MARGIN + crate::SIDE_PANEL_WIDTH + STATS_MARGIN) as i32;
+		self.draw_text(format!("Auto-stop: {}", if self.auto_stop { "on" } else { "off" }).as_str(), panel_x, y, crate::COLOR_WHITE);
+		y += line_height as i32;
+		if let Some(stop_reason) = self.stop_reason {
+			self.draw_text(stop_reason.to_string().as_str(), panel_x, y, crate::COLOR_YELLOW);
+			y += line_height as i32;
+		}
+		if let Some(notice) = self.notice.clone() {
+			self.draw_text(notice.as_str(), panel_x, y, crate::COLOR_YELLOW);
+			y += line_height as i32;
+		}
+
+		// Statistics
+		let mut lines = vec![
+			format!("Rule: {}", self.engine.rule()),
+			format!("Population: {}", self.engine.population()),
+			format!("Visible: {}", self.visible_population)
+		];
+		if let Some(stochastic) = self.engine.stochastic() {
+			lines.push(format!("Random: seed \"{}\", noise {}", stochastic.seed, stochastic.noise));
+		}
+		if self.engine.rule().species > 1 {
+			lines.push(format!("Pencil: species {} of {}", self.paint_state, self.engine.rule().species));
+		} else if self.engine.rule().states > 2 {
+			lines.push(format!("Pencil: state {} of {}", self.paint_state, self.engine.rule().states - 1));
+		}
+		let observed = if self.selection.is_some() { "Selection" } else { "Pattern" };
+		lines.push(match self.classifier.classification() {
+			Some((classification, _)) => format!("{}: {}", observed, classification),
+			None => format!("{}: evolving", observed)
+		});
+		if let Some(record) = self.statistics.last() {
+			lines.push(format!("Births: {} :: Deaths: {}", record.births, record.deaths));
+			lines.push(match record.bounding_box {
+				Some(bounding_box) => format!("Bounding box: {}x{}", bounding_box.width(), bounding_box.height()),
+				None => "Bounding box: -".to_string()
+			});
+			lines.push(format!("Live chunks: {}", record.live_chunks));
+		}
+
+		for line in lines.iter() {
+			self.draw_text(line, panel_x, y, crate::COLOR_WHITE);
+			y += line_height as i32;
+		}
+
+		// Population of each species, in its color
+		if self.engine.rule().species > 1 {
+			let (width, _) = self.draw_text("Species:", panel_x, y, crate::COLOR_WHITE);
+			let mut x = panel_x + width as i32;
+			for species in 1..=self.engine.rule().species {
+				let population = self.engine.state_population().get(species as usize).copied().unwrap_or(0);
+				let (width, _) = self.draw_text(format!(" {}", population).as_str(), x, y, crate::state_color(species, self.engine.rule()));
+				x += width as i32;
 			}
+			y += line_height as i32;
 		}
 
-		if it_start.elapsed().as_secs() > 1 {
-			println!("[WARNING] Iteration took {}ms [{}s]", it_start.elapsed().as_millis(), it_start.elapsed().as_millis() as _Float32 / 1000 as _Float32);
+		// Rules of the regions, in their tint
+		for (index, rule) in self.engine.region_rules().clone().iter().enumerate() {
+			self.draw_text(format!("Region: {}", rule).as_str(), panel_x, y, REGION_COLORS[index % REGION_COLORS.len()]);
+			y += line_height as i32;
 		}
-		return (new_generation, new_change_matrix);
+
+		let graph_rect = Rect::new(panel_x, y + STATS_MARGIN as i32, crate::SIDE_PANEL_WIDTH, STATS_GRAPH_HEIGHT);
+		self.statistics.draw_population_graph(&mut self.canvas, graph_rect);
 	}
 
-	fn has_changed_neighbors(&self, i: u32, j: u32) -> bool {
-		let target_i: i32 = i as i32;
-		let target_j: i32 = j as i32;
+	fn draw_selection(&mut self) {
+		let selection = match self.selection {
+			Some(selection) => selection,
+			None => return
+		};
+
+		// The selection is sheared along with the hexagonal view, so its cells are tinted one by one
+		if self.hexagonal() {
+			self.canvas.set_clip_rect(Rect::new(crate::H_MARGIN as i32, crate::V_MARGIN as i32, crate::GRID_WIDTH, crate::GRID_HEIGHT));
+			self.canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+			self.canvas.set_draw_color(Color::RGBA(crate::COLOR_BLUE.r, crate::COLOR_BLUE.g, crate::COLOR_BLUE.b, DEBUG_ALPHA));
+			for (row, col, drawing_rect) in self.view_cells() {
+				if (selection.top..=selection.bottom).contains(&(row as u32)) && (selection.left..=selection.right).contains(&(col as u32)) {
+					let _ = self.canvas.fill_rect(drawing_rect);
+				}
+			}
+			self.canvas.set_blend_mode(sdl2::render::BlendMode::None);
+			self.canvas.set_clip_rect(None);
+			return;
+		}
 
+		// Clip the selection to the viewport
+		let from_row = u32::max(selection.top, self.top_left_row);
+		let to_row = u32::min(selection.bottom + 1, self.top_left_row + crate::VIEW_ROWS);
+		let from_col = u32::max(selection.left, self.top_left_col);
+		let to_col = u32::min(selection.right + 1, self.top_left_col + crate::VIEW_COLS);
+		if from_row >= to_row || from_col >= to_col {
+			return;
+		}
 
-	
-		let search_i_from = i32::max(0, target_i - 1);
-		let search_i_to = i32::min(crate::SUB_MATRIX_SIZE as i32, target_i + 2);
-		let search_j_from = i32::max(0, target_j - 1);
-		let search_j_to = i32::min(crate::SUB_MATRIX_SIZE as i32, target_j + 2);
-	
-		for row in search_i_from..search_i_to {
-			for col in search_j_from..search_j_to {
-				if row != target_i || col != target_j {
-					if self.change_matrix[row as usize][col as usize] == true {
-						return true;
+		let selection_rect = Rect::new((crate::H_MARGIN + (from_col - self.top_left_col) * crate::SIZE) as i32, (crate::V_MARGIN + (from_row - self.top_left_row) * crate::SIZE) as i32, (to_col - from_col) * crate::SIZE, (to_row - from_row) * crate::SIZE);
+		self.canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+		self.canvas.set_draw_color(Color::RGBA(crate::COLOR_BLUE.r, crate::COLOR_BLUE.g, crate::COLOR_BLUE.b, DEBUG_ALPHA));
+		let _ = self.canvas.fill_rect(selection_rect);
+		self.canvas.set_blend_mode(sdl2::render::BlendMode::None);
+		self.canvas.set_draw_color(crate::COLOR_BLUE);
+		let _ = self.canvas.draw_rect(selection_rect);
+	}
+
+	fn draw_lines(&mut self) {
+		// Main grid lines
+		self.canvas.set_draw_color(crate::COLOR_BLACK_2);
+		if self.hexagonal() { // Offset rows, each row has its own vertical segments
+			for row in 0..crate::VIEW_ROWS {
+				let offset = (row % 2 * crate::SIZE / 2) as i32;
+				for col in 1..=crate::VIEW_COLS {
+					let x = (crate::H_MARGIN + crate::SIZE * col) as i32 - offset;
+					if x < (crate::H_MARGIN + crate::GRID_WIDTH) as i32 {
+						let _ = self.canvas.draw_line(Point::new(x, (crate::V_MARGIN + crate::SIZE * row) as i32), Point::new(x, (crate::V_MARGIN + crate::SIZE * (row + 1)) as i32 - 1));
 					}
 				}
 			}
+		} else {
+			for row in 1..crate::VIEW_COLS {
+				let start_point = Point::new((crate::H_MARGIN + crate::SIZE * row) as i32, crate::V_MARGIN as i32);
+				let end_point = Point::new((crate::H_MARGIN + crate::SIZE * row) as i32, (crate::V_MARGIN + (crate::VIEW_ROWS * crate::SIZE)-1) as i32);
+				let _ = self.canvas.draw_line(start_point, end_point);
+			}
+		}
+		for row in 1..crate::VIEW_ROWS {
+			let start_point = Point::new(crate::H_MARGIN as i32, (crate::V_MARGIN + crate::SIZE * row) as i32);
+			let end_point = Point::new((crate::H_MARGIN + (crate::VIEW_COLS * crate::SIZE)-1) as i32, (crate::V_MARGIN + crate::SIZE * row) as i32);
+			let _ = self.canvas.draw_line(start_point, end_point);
 		}
 		
-		return false;
+		// Outer grid lines
+		self.canvas.set_draw_color(crate::COLOR_BLACK_3);
+		let _ = self.canvas.draw_rect(Rect::new(crate::H_MARGIN as i32, crate::V_MARGIN as i32, crate::VIEW_COLS * crate::SIZE, crate::VIEW_ROWS * crate::SIZE));
 	}
 
-	fn bruteforce_changes(&mut self) {
-		let it_start = Instant::now();
-
-		let mut new_matrix = vec![vec![true; crate::SUB_MATRIX_SIZE as usize]; crate::SUB_MATRIX_SIZE as usize];
-		new_matrix.par_iter_mut().enumerate().for_each(|(sub_row, row_content)| {
-			row_content.par_iter_mut().enumerate().for_each(|(sub_col, elem)| {
-				let mut has_alive_cells = false;
-				for row in sub_row*(SUB_MATRIX_CHUNK_SIZE as usize)..(sub_row+1)*(SUB_MATRIX_CHUNK_SIZE as usize) {
-					for col in sub_col*(SUB_MATRIX_CHUNK_SIZE as usize)..(sub_col+1)*(SUB_MATRIX_CHUNK_SIZE as usize) {
-						if self.generation[row as usize][col as usize] == true { // Alive
-							has_alive_cells = true;
-							break;
-						}
-					}
-					if has_alive_cells {
-						break;
-					}
-				}
-				*elem = has_alive_cells;
-			})
-		});
-
-		self.change_matrix = new_matrix;
+	fn draw_buttons(&mut self) {
+		// Text buttons
+		self.btn_start_simulation.draw(&mut self.canvas, self.main_font.as_ref().unwrap());
+		self.btn_pause_resume_simulation.draw(&mut self.canvas, self.main_font.as_ref().unwrap());
+		self.btn_clear_generation.draw(&mut self.canvas, self.main_font.as_ref().unwrap());
+		self.btn_abort_simulation.draw(&mut self.canvas, self.main_font.as_ref().unwrap());
+		self.btn_abort_n_save_simulation.draw(&mut self.canvas, self.main_font.as_ref().unwrap());
+		self.btn_step_simulation.draw(&mut self.canvas, self.main_font.as_ref().unwrap());
 		
-		println!("[INFO] Changes took {}ms [{}s] to load", it_start.elapsed().as_millis(), it_start.elapsed().as_millis() as _Float32 / 1000 as _Float32);
+		// Icon buttons
+		self.btn_tool_pencil.draw(&mut self.canvas);
+		self.btn_tool_eraser.draw(&mut self.canvas);
+		self.btn_tool_hand.draw(&mut self.canvas);
 	}
 }
 
@@ -0,0 +1,417 @@
+use std::collections::HashSet;
+use std::fs;
+use std::time::Instant;
+
+use crate::rle;
+use crate::rle::Pattern;
+use crate::rule::Neighborhood;
+use crate::rule::Rule;
+
+
+const DEFAULT_SIZE: u32 = 6;
+const DEFAULT_RESULTS: usize = 5;
+const DEFAULT_OUTPUT: &str = "ships";
+const UNKNOWN: i8 = -1;
+
+const USAGE: &str = "Usage: conway --ship-search --period <generations> [--dx <cells>] [--dy <cells>] [--width <cells>] [--height <cells>]
+                          [--symmetry none|mirror] [--rule <rule>] [--results <amount>] [--output <directory>]
+Searches spaceships moving (dx, dy) cells every period, or oscillators if both are 0, that fit in the box in every phase";
+
+
+#[derive(PartialEq, Clone, Copy)]
+enum Symmetry {
+	None,
+	Mirror // Left and right halves are reflections of each other, only for spaceships moving vertically
+}
+
+struct Options {
+	period: u32,
+	dx: i64,
+	dy: i64,
+	width: u32,
+	height: u32,
+	symmetry: Symmetry,
+	rule: Rule,
+	results: usize,
+	output: String
+}
+
+fn parse_value<T: std::str::FromStr>(args: &mut std::slice::Iter<String>, option: &str) -> Result<T, String> {
+	let value = args.next().ok_or(format!("missing value for {}\n{}", option, USAGE))?;
+	value.parse::<T>().map_err(|_| format!("invalid value \"{}\" for {}", value, option))
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+	let mut period = None;
+	let mut options = Options {
+		period: 0,
+		dx: 0,
+		dy: 0,
+		width: DEFAULT_SIZE,
+		height: DEFAULT_SIZE,
+		symmetry: Symmetry::None,
+		rule: Rule::life(),
+		results: DEFAULT_RESULTS,
+		output: DEFAULT_OUTPUT.to_string()
+	};
+
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--ship-search" => {},
+			"--period" => period = Some(parse_value(&mut args, arg)?),
+			"--dx" => options.dx = parse_value(&mut args, arg)?,
+			"--dy" => options.dy = parse_value(&mut args, arg)?,
+			"--width" => options.width = parse_value(&mut args, arg)?,
+			"--height" => options.height = parse_value(&mut args, arg)?,
+			"--symmetry" => options.symmetry = match args.next().map(|value| value.as_str()) {
+				Some("none") => Symmetry::None,
+				Some("mirror") => Symmetry::Mirror,
+				_ => return Err(format!("--symmetry must be none or mirror\n{}", USAGE))
+			},
+			"--rule" => options.rule = Rule::parse(parse_value::<String>(&mut args, arg)?.as_str())?,
+			"--results" => options.results = parse_value(&mut args, arg)?,
+			"--output" => options.output = parse_value(&mut args, arg)?,
+			_ => return Err(format!("unknown option \"{}\"\n{}", arg, USAGE))
+		}
+	}
+
+	options.period = period.ok_or(format!("missing --period\n{}", USAGE))?;
+	if options.period == 0 {
+		return Err("the period must be at least 1".to_string());
+	}
+	if options.width == 0 || options.height == 0 {
+		return Err("the box must be at least 1x1".to_string());
+	}
+	if options.symmetry == Symmetry::Mirror && options.dx != 0 {
+		return Err("mirror symmetric spaceships can only move vertically, --dx must be 0".to_string());
+	}
+	if options.rule.neighborhood != Neighborhood::Moore {
+		return Err(format!("{} doesn't use the 8 neighbors, only rules on them can be searched", options.rule));
+	}
+	if options.rule.table.is_some() {
+		return Err(format!("{} is a rule table, only Life-like rules can be searched", options.rule));
+	}
+	if options.rule.block.is_some() {
+		return Err(format!("{} is a block rule, only Life-like rules can be searched", options.rule));
+	}
+	if options.rule.range.is_some() {
+		return Err(format!("{} is a Larger than Life rule, only rules on the 8 neighbors can be searched", options.rule));
+	}
+	if options.rule.isotropic.is_some() {
+		return Err(format!("{} is non-totalistic, only rules that depend on the amount of neighbors can be searched", options.rule));
+	}
+	if options.rule.states > 2 {
+		return Err(format!("{} has more than 2 states, only two-state rules can be searched", options.rule));
+	}
+	if options.rule.birth[0] {
+		return Err(format!("{} makes empty space come alive, spaceships can't exist in it", options.rule));
+	}
+	Ok(options)
+}
+
+// A cell of the next generation, given its neighborhood in the current one. Cells outside the box are always dead, so only
+// the variables inside it are kept, each once with how many neighbors it is and whether it is the cell or its next state
+struct Constraint {
+	variables: Vec<(usize, i8, bool, bool)> // (variable, neighbors, is the cell, is the next state)
+}
+
+// Backtracking search over the cells of every phase, in the style of lifesrc: each cell is a variable, each neighborhood
+// a constraint, and cells forced by a constraint are set right away so contradictions are found early
+struct Search<'o> {
+	options: &'o Options,
+	constraints: Vec<Constraint>,
+	watches: Vec<Vec<usize>>, // Constraints each variable is part of
+	sides: Vec<Vec<usize>>, // Cells of the top side, and of the left one, in every phase: at least one of each must be alive
+	values: Vec<i8>, // 0, 1 or UNKNOWN
+	trail: Vec<usize>, // Variables in the order they were set, to undo them when backtracking
+	order: Vec<usize>, // Order the variables are branched on
+	seen: HashSet<Vec<(u32, u32)>>,
+	results: Vec<Vec<Vec<(u32, u32)>>> // Every phase of each result
+}
+
+impl<'o> Search<'o> {
+	fn new(options: &'o Options) -> Self {
+		let variable_width = if options.symmetry == Symmetry::Mirror { options.width.div_ceil(2) } else { options.width } as usize;
+		let variables = options.period as usize * options.height as usize * variable_width;
+		let mut search = Search {
+			options,
+			constraints: Vec::new(),
+			watches: vec![Vec::new(); variables],
+			sides: Vec::new(),
+			values: vec![UNKNOWN; variables],
+			trail: Vec::new(),
+			order: Vec::new(),
+			seen: HashSet::new(),
+			results: Vec::new()
+		};
+
+		// Every cell that can be alive in the next phase, or whose neighborhood has cells of the box
+		let (period, width, height) = (options.period as i64, options.width as i64, options.height as i64);
+		for phase in 0..period {
+			for row in -1 - options.dy.abs()..=height + options.dy.abs() {
+				for col in -1 - options.dx.abs()..=width + options.dx.abs() {
+					let mut variables: Vec<(usize, i8, bool, bool)> = Vec::new();
+					let mut add = |variable: Option<usize>, neighbors: i8, cell: bool, next: bool| {
+						if let Some(variable) = variable {
+							match variables.iter_mut().find(|entry| entry.0 == variable) {
+								Some(entry) => *entry = (variable, entry.1 + neighbors, entry.2 || cell, entry.3 || next),
+								None => variables.push((variable, neighbors, cell, next))
+							}
+						}
+					};
+					for index in 0..9 {
+						let variable = search.variable(phase, row + index / 3 - 1, col + index % 3 - 1);
+						if index == 4 { add(variable, 0, true, false) } else { add(variable, 1, false, false) }
+					}
+					// The last phase evolves into the first one, moved by the displacement
+					add(if phase + 1 < period { search.variable(phase + 1, row, col) } else { search.variable(0, row - options.dy, col - options.dx) }, 0, false, true);
+					if variables.is_empty() {
+						continue;
+					}
+
+					for (variable, ..) in variables.iter() {
+						search.watches[*variable].push(search.constraints.len());
+					}
+					search.constraints.push(Constraint { variables });
+				}
+			}
+		}
+
+		// Any pattern can be moved up and left until it touches both sides, so the other positions aren't searched. This also
+		// rules out the empty universe. Mirror symmetric patterns can't be moved sideways
+		search.sides.push((0..period).flat_map(|phase| (0..width).map(move |col| (phase, col))).filter_map(|(phase, col)| search.variable(phase, 0, col)).collect());
+		if options.symmetry == Symmetry::None {
+			search.sides.push((0..period).flat_map(|phase| (0..height).map(move |row| (phase, row))).filter_map(|(phase, row)| search.variable(phase, row, 0)).collect());
+		}
+
+		// Across the movement first, so the constraints of each line are completed as soon as possible
+		let across_rows = options.dy.abs() <= options.dx.abs();
+		let (outer, inner) = if across_rows { (height, width) } else { (width, height) };
+		for line in 0..outer {
+			for phase in 0..period {
+				for position in 0..inner {
+					let (row, col) = if across_rows { (line, position) } else { (position, line) };
+					if let Some(variable) = search.variable(phase, row, col) {
+						if !search.order.contains(&variable) {
+							search.order.push(variable);
+						}
+					}
+				}
+			}
+		}
+		search
+	}
+
+	fn variable(&self, phase: i64, row: i64, col: i64) -> Option<usize> {
+		let (width, height) = (self.options.width as i64, self.options.height as i64);
+		if row < 0 || col < 0 || row >= height || col >= width {
+			return None;
+		}
+
+		let (col, variable_width) = match self.options.symmetry {
+			Symmetry::None => (col, width),
+			Symmetry::Mirror => (i64::min(col, width - 1 - col), (width + 1) / 2)
+		};
+		Some(((phase * height + row) * variable_width + col) as usize)
+	}
+
+	fn value(&self, cell: Option<usize>) -> i8 {
+		cell.map_or(0, |variable| self.values[variable])
+	}
+
+	// Whether the next state can match with the alive neighbors somewhere between alive and alive + unknown
+	fn feasible(&self, alive: i8, unknown: i8, cell: i8, next: i8) -> bool {
+		let states: &[bool] = match cell {
+			UNKNOWN => &[false, true],
+			1 => &[true],
+			_ => &[false]
+		};
+		states.iter().any(|state| (alive..=alive + unknown).any(|neighbors| next == UNKNOWN || self.options.rule.next_state(*state, neighbors) == (next == 1)))
+	}
+
+	// Alive neighbors, unknown neighbors, state of the cell and of its next state
+	fn summary(&self, constraint: &Constraint) -> (i8, i8, i8, i8) {
+		let (mut alive, mut unknown, mut cell, mut next) = (0, 0, 0, 0);
+		for (variable, neighbors, is_cell, is_next) in constraint.variables.iter() {
+			let value = self.values[*variable];
+			match value {
+				UNKNOWN => unknown += neighbors,
+				_ => alive += neighbors * value
+			}
+			if *is_cell {
+				cell = value;
+			}
+			if *is_next {
+				next = value;
+			}
+		}
+		(alive, unknown, cell, next)
+	}
+
+	// Sets the variable and every one it forces. False on a contradiction, the caller undoes the trail either way
+	fn assign(&mut self, variable: usize, value: i8) -> bool {
+		self.values[variable] = value;
+		self.trail.push(variable);
+		self.propagate(self.watches[variable].clone()) && self.check_sides()
+	}
+
+	// Checks the constraints, setting the cells that can only have one value and checking their constraints in turn.
+	// The unknown neighbors are counted as independent cells, so a wrong partial assignment may go through, but never
+	// the other way around. Complete assignments are checked exactly
+	fn propagate(&mut self, mut pending: Vec<usize>) -> bool {
+		while let Some(constraint) = pending.pop() {
+			let (alive, unknown, cell, next) = self.summary(&self.constraints[constraint]);
+			if !self.feasible(alive, unknown, cell, next) {
+				return false;
+			}
+			if unknown == 0 && cell != UNKNOWN && next != UNKNOWN {
+				continue;
+			}
+
+			for index in 0..self.constraints[constraint].variables.len() {
+				let (variable, neighbors, is_cell, is_next) = self.constraints[constraint].variables[index];
+				if self.values[variable] != UNKNOWN {
+					continue;
+				}
+				let feasible_with = |value: i8| self.feasible(alive + neighbors * value, unknown - neighbors, if is_cell { value } else { cell }, if is_next { value } else { next });
+				match (feasible_with(0), feasible_with(1)) {
+					(false, false) => return false,
+					(true, false) => self.values[variable] = 0,
+					(false, true) => self.values[variable] = 1,
+					(true, true) => continue
+				}
+				self.trail.push(variable);
+				pending.extend(self.watches[variable].iter());
+			}
+		}
+		true
+	}
+
+	// Sets the last unknown cell of a side alive if the rest are dead
+	fn check_sides(&mut self) -> bool {
+		for side in 0..self.sides.len() {
+			if self.sides[side].iter().any(|variable| self.values[*variable] == 1) {
+				continue;
+			}
+			let mut unknown = self.sides[side].iter().filter(|variable| self.values[**variable] == UNKNOWN);
+			let last = match (unknown.next().copied(), unknown.next()) {
+				(None, _) => return false,
+				(Some(variable), None) => variable,
+				_ => continue
+			};
+			if !self.assign(last, 1) {
+				return false;
+			}
+		}
+		true
+	}
+
+	fn undo(&mut self, length: usize) {
+		while self.trail.len() > length {
+			let variable = self.trail.pop().unwrap_or_default();
+			self.values[variable] = UNKNOWN;
+		}
+	}
+
+	// Depth first search, dead cells first so the smallest patterns come up before the rest
+	fn extend(&mut self, position: usize) {
+		if self.results.len() >= self.options.results {
+			return;
+		}
+
+		let variable = match self.order[position..].iter().find(|variable| self.values[**variable] == UNKNOWN) {
+			Some(variable) => *variable,
+			None => return self.record_solution()
+		};
+		for value in [0, 1] {
+			let length = self.trail.len();
+			if self.assign(variable, value) {
+				self.extend(position + 1);
+			}
+			self.undo(length);
+		}
+	}
+
+	fn record_solution(&mut self) {
+		let mut phases = Vec::new();
+		for phase in 0..self.options.period as i64 {
+			let mut cells = Vec::new();
+			for row in 0..self.options.height as i64 {
+				for col in 0..self.options.width as i64 {
+					if self.value(self.variable(phase, row, col)) == 1 {
+						cells.push((row, col));
+					}
+				}
+			}
+			phases.push(normalize(&cells));
+		}
+
+		// Patterns that repeat earlier also satisfy every constraint
+		if phases[1..].iter().any(|phase| *phase == phases[0]) {
+			return;
+		}
+		// The same pattern is found starting from each of its phases, and rotated or reflected if the box allows it
+		let key = phases.iter()
+			.flat_map(|phase| (0..8).map(move |transformation| normalize(&phase.iter().map(|(row, col)| transform(transformation, *row as i64, *col as i64)).collect::<Vec<(i64, i64)>>())))
+			.min()
+			.unwrap_or_default();
+		if self.seen.insert(key) {
+			self.results.push(phases);
+		}
+	}
+}
+
+// Cells moved to touch the top and left sides, sorted
+fn normalize(cells: &[(i64, i64)]) -> Vec<(u32, u32)> {
+	let top = cells.iter().map(|cell| cell.0).min().unwrap_or(0);
+	let left = cells.iter().map(|cell| cell.1).min().unwrap_or(0);
+	let mut normalized: Vec<(u32, u32)> = cells.iter().map(|(row, col)| ((row - top) as u32, (col - left) as u32)).collect();
+	normalized.sort();
+	normalized
+}
+
+// One of the 8 rotations and reflections
+fn transform(transformation: usize, row: i64, col: i64) -> (i64, i64) {
+	let (row, col) = if transformation & 4 != 0 { (col, row) } else { (row, col) };
+	(if transformation & 1 != 0 { -row } else { row }, if transformation & 2 != 0 { -col } else { col })
+}
+
+// Searches spaceships or oscillators of a Life-like rule and saves them as RLE files that can be opened in the editor
+pub fn run(args: &[String]) -> Result<(), String> {
+	let options = parse_options(args)?;
+	fs::create_dir_all(options.output.as_str()).map_err(|error| format!("could not create {}: {}", options.output, error))?;
+
+	let moving = options.dx != 0 || options.dy != 0;
+	let kind = if moving { "spaceship" } else { "oscillator" };
+	println!("Searching period {} {}s{} in a {}x{} box under {}", options.period, kind,
+		if moving { format!(" moving ({}, {})", options.dx, options.dy) } else { String::new() }, options.width, options.height, options.rule);
+
+	let start = Instant::now();
+	let mut search = Search::new(&options);
+	let constraints = (0..search.constraints.len()).collect();
+	if search.propagate(constraints) {
+		search.extend(0);
+	}
+	println!("Found {} in {:.2}s", search.results.len(), start.elapsed().as_secs_f32());
+
+	for (index, phases) in search.results.iter().enumerate() {
+		let cells = &phases[0];
+		let pattern = Pattern {
+			width: cells.iter().map(|cell| cell.1 + 1).max().unwrap_or(0),
+			height: cells.iter().map(|cell| cell.0 + 1).max().unwrap_or(0),
+			cells: cells.clone(),
+			states: Vec::new(),
+			rule: Some(options.rule.to_string())
+		};
+		let comments = vec![match moving {
+			true => format!("Period {} spaceship moving ({}, {}) every period", options.period, options.dx, options.dy),
+			false => format!("Period {} oscillator", options.period)
+		}];
+		let path = format!("{}/{}_{}.rle", options.output, kind, index + 1);
+		rle::save(path.as_str(), &pattern, &comments)?;
+		print!("\n{}", rle::to_string(&pattern, &comments));
+		println!("Saved to {}", path);
+	}
+	Ok(())
+}
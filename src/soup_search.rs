@@ -0,0 +1,151 @@
+use std::fs;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use rand::Rng;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use rayon::iter::IntoParallelIterator;
+use rayon::iter::ParallelIterator;
+
+use crate::census::Census;
+use crate::rle;
+use crate::rle::Pattern;
+use crate::rule::Rule;
+use crate::stabilisation;
+use crate::stochastic::hash_name;
+
+
+const SOUP_SIZE: u32 = 16; // Width and height of the random soups
+const MAX_SOUP_GENERATIONS: i32 = 20000;
+const RARE_OSCILLATOR_PERIOD: u32 = 4; // Oscillators with this period or higher are saved
+
+const DEFAULT_SOUPS: u32 = 1000;
+const DEFAULT_OUTPUT: &str = "soups";
+
+const USAGE: &str = "Usage: conway --soup-search [--seed <prefix>] [--soups <amount>] [--output <directory>]";
+
+
+struct Options {
+	seed: String,
+	soups: u32,
+	output: String
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+	// A different prefix for each run by default, it's printed so the run can be repeated
+	let mut seed = format!("s{}_", SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs()));
+	let mut soups = DEFAULT_SOUPS;
+	let mut output = DEFAULT_OUTPUT.to_string();
+
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--soup-search" => {},
+			"--seed" => seed = args.next().ok_or(format!("missing value for --seed\n{}", USAGE))?.to_string(),
+			"--soups" => {
+				let value = args.next().ok_or(format!("missing value for --soups\n{}", USAGE))?;
+				soups = value.parse::<u32>().map_err(|_| format!("invalid amount of soups \"{}\"", value))?;
+			},
+			"--output" => output = args.next().ok_or(format!("missing value for --output\n{}", USAGE))?.to_string(),
+			_ => return Err(format!("unknown option \"{}\"\n{}", arg, USAGE))
+		}
+	}
+
+	Ok(Options {
+		seed,
+		soups,
+		output
+	})
+}
+
+// Random soup with half of its cells alive, fully determined by its name
+pub fn generate_soup(name: &str) -> Pattern {
+	let mut rng = ChaCha8Rng::seed_from_u64(hash_name(name));
+	let mut cells = Vec::new();
+	for row in 0..SOUP_SIZE {
+		for col in 0..SOUP_SIZE {
+			if rng.gen_bool(0.5) {
+				cells.push((row, col));
+			}
+		}
+	}
+
+	Pattern {
+		width: SOUP_SIZE,
+		height: SOUP_SIZE,
+		cells,
+		states: Vec::new(),
+		rule: None
+	}
+}
+
+// Spaceships other than the glider, high period oscillators and objects that couldn't be identified
+fn is_rare(apgcode: &str) -> bool {
+	match apgcode.split_once('_') {
+		Some(("xq4", "153")) => false,
+		Some((prefix, _)) if prefix.starts_with("xq") => true,
+		Some((prefix, _)) if prefix.starts_with("xp") => prefix[2..].parse::<u32>().map_or(true, |period| period >= RARE_OSCILLATOR_PERIOD),
+		Some((prefix, _)) => prefix.starts_with("zz"),
+		None => true
+	}
+}
+
+struct SoupResult {
+	name: String,
+	census: Census,
+	stabilised: bool
+}
+
+// Runs the soup until its population repeats or the generation limit is reached, and takes the census of what's left
+fn run_soup(name: String) -> SoupResult {
+	let mut engine = stabilisation::load_centered(&generate_soup(name.as_str())).expect("the soup fits in the universe");
+	let outcome = stabilisation::run(&mut engine, MAX_SOUP_GENERATIONS);
+
+	let mut census = match outcome.period {
+		Some(period) => Census::take_periodic(&mut engine, period),
+		None => Census::take(&engine, None)
+	};
+	census.merge(&outcome.escaped);
+	SoupResult {
+		name,
+		census,
+		stabilised: outcome.period.is_some()
+	}
+}
+
+// Searches random soups in parallel, tallying the objects they decay into and saving the rare ones as RLE
+pub fn run(args: &[String]) -> Result<(), String> {
+	let options = parse_options(args)?;
+	fs::create_dir_all(options.output.as_str()).map_err(|error| format!("could not create {}: {}", options.output, error))?;
+	println!("Searching {} soups with the seed prefix \"{}\"", options.soups, options.seed);
+
+	let start = Instant::now();
+	let results: Vec<SoupResult> = (0..options.soups).into_par_iter().map(|index| run_soup(format!("{}{}", options.seed, index))).collect();
+
+	let mut census = Census::new(Rule::life());
+	let mut rare_objects = 0;
+	for result in results.iter() {
+		census.merge(&result.census);
+		for entry in result.census.entries.iter().filter(|entry| is_rare(entry.apgcode.as_str())) {
+			let path = format!("{}/{}_{}.rle", options.output, entry.apgcode, result.name);
+			let comments = vec![
+				entry.apgcode.clone(),
+				format!("Found in the {}x{} soup \"{}\"", SOUP_SIZE, SOUP_SIZE, result.name)
+			];
+			rle::save(path.as_str(), &entry.sample_pattern(), &comments)?;
+			println!("[INFO] {} found in soup \"{}\", saved to {}", entry.apgcode, result.name, path);
+			rare_objects += 1;
+		}
+	}
+
+	let unstable = results.iter().filter(|result| !result.stabilised).count();
+	println!("Soups: {} in {:.2}s ({} didn't stabilise before generation {})", options.soups, start.elapsed().as_secs_f32(), unstable, MAX_SOUP_GENERATIONS);
+	println!("Rare objects: {}", rare_objects);
+	census.print();
+
+	let census_path = format!("{}/census.csv", options.output);
+	census.save(census_path.as_str())?;
+	println!("Census saved to {}", census_path);
+	Ok(())
+}
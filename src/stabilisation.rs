@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use crate::census;
+use crate::census::Census;
+use crate::engine::BoundingBox;
+use crate::engine::Engine;
+use crate::rle::Pattern;
+
+
+pub const UNIVERSE_SIZE: u32 = 512; // Patterns are placed in the middle
+const ESCAPE_MARGIN: u32 = 16; // Spaceships getting this close to the edge are removed, so they don't crash into it
+const ESCAPE_CHECK_INTERVAL: i32 = 32; // Generations between each search of spaceships flying away
+const ISOLATION_DISTANCE: u32 = 16; // Spaceships this far away from every other object have escaped
+const MAX_POPULATION_PERIOD: usize = 60; // Longest period the population can repeat with to consider the pattern stable
+const STABILITY_WINDOW: usize = MAX_POPULATION_PERIOD * 6; // Generations the population has to keep repeating
+
+
+// How a pattern ended up after running it until it stabilised
+pub struct Outcome {
+	pub lifespan: i32, // Generation since which the population repeats
+	pub period: Option<u32>, // Period of the population, None if it didn't stabilise within the generation limit
+	pub max_bounding_box: (u32, u32), // Widest (width, height) the pattern reached, not counting escaped spaceships
+	pub escaped: Census // Spaceships that were taken out of the universe
+}
+
+// Universe with the pattern in its middle, ready to be run
+pub fn load_centered(pattern: &Pattern) -> Result<Engine, String> {
+	let mut engine = Engine::with_size(UNIVERSE_SIZE);
+	engine.load_pattern(pattern, UNIVERSE_SIZE.saturating_sub(pattern.height) / 2, UNIVERSE_SIZE.saturating_sub(pattern.width) / 2)?;
+	Ok(engine)
+}
+
+// Period of the last populations if they repeat, a multiple of the step. Gliders flying away keep the population constant, so
+// they don't prevent it
+fn population_period(populations: &[u64], step: usize) -> Option<usize> {
+	if populations.len() < STABILITY_WINDOW + MAX_POPULATION_PERIOD {
+		return None;
+	}
+
+	let start = populations.len() - STABILITY_WINDOW;
+	(step..=MAX_POPULATION_PERIOD).step_by(step).find(|period| (start..populations.len()).all(|index| populations[index] == populations[index - period]))
+}
+
+// First index since which the populations repeat with the period
+fn settled_since(populations: &[u64], period: usize) -> usize {
+	let mut index = populations.len() - period;
+	while index > 0 && populations[index - 1] == populations[index - 1 + period] {
+		index -= 1;
+	}
+	index
+}
+
+fn bounding_box_of(cells: &[(u32, u32, u8)]) -> BoundingBox {
+	BoundingBox {
+		top: cells.iter().map(|cell| cell.0).min().unwrap_or(0),
+		left: cells.iter().map(|cell| cell.1).min().unwrap_or(0),
+		bottom: cells.iter().map(|cell| cell.0).max().unwrap_or(0),
+		right: cells.iter().map(|cell| cell.1).max().unwrap_or(0)
+	}
+}
+
+// Takes the spaceships that are flying away (or close to the edge) out of the universe, adding them to the census.
+// Anything else stays, it may still be part of a reaction
+fn remove_escaped_spaceships(engine: &mut Engine, escaped: &mut Census, identified: &mut HashMap<Vec<(u32, u32, u8)>, String>) {
+	let objects = census::separate(&engine.state_cells(None));
+	let bounding_boxes: Vec<BoundingBox> = objects.iter().map(|object| bounding_box_of(object)).collect();
+
+	for (index, object) in objects.iter().enumerate() {
+		let bounding_box = bounding_boxes[index];
+		let near_edge = bounding_box.top < ESCAPE_MARGIN || bounding_box.left < ESCAPE_MARGIN || bounding_box.bottom >= UNIVERSE_SIZE - ESCAPE_MARGIN || bounding_box.right >= UNIVERSE_SIZE - ESCAPE_MARGIN;
+		let isolated = bounding_boxes.iter().enumerate().all(|(other_index, other)| {
+			other_index == index
+				|| other.top > bounding_box.bottom + ISOLATION_DISTANCE || bounding_box.top > other.bottom + ISOLATION_DISTANCE
+				|| other.left > bounding_box.right + ISOLATION_DISTANCE || bounding_box.left > other.right + ISOLATION_DISTANCE
+		});
+		if !near_edge && !isolated {
+			continue;
+		}
+
+		// Equal shapes are only identified once
+		let apgcode = identified.entry(census::normalize(object)).or_insert_with(|| census::apgcode(object, engine.rule())).clone();
+		if !apgcode.starts_with("xq") {
+			continue;
+		}
+
+		escaped.add_object(apgcode, object);
+		for (row, col, _) in object {
+			engine.set_state(*row as usize, *col as usize, 0);
+		}
+	}
+}
+
+// Runs the engine until its population repeats, dies out or the generation limit is reached
+pub fn run(engine: &mut Engine, max_generations: i32) -> Outcome {
+	let first_generation = engine.generation_number();
+	let mut escaped = Census::new(engine.rule().clone());
+	let mut identified = HashMap::new();
+	let mut escaped_population = 0; // Removed spaceships still count, taking them out of the universe isn't a death
+	let mut populations = vec![engine.population()];
+	let step = if engine.rule().block.is_some() { 2 } else { 1 }; // The grid of blocks rules takes 2 generations to come back
+	let mut max_bounding_box = engine.bounding_box().map_or((0, 0), |bounding_box| (bounding_box.width(), bounding_box.height()));
+
+	while engine.generation_number() < max_generations {
+		if engine.population() == 0 {
+			return Outcome {
+				lifespan: first_generation + settled_since(&populations, 1) as i32,
+				period: Some(1),
+				max_bounding_box,
+				escaped
+			};
+		}
+
+		engine.iterate();
+		let mut bounding_box = engine.bounding_box();
+		if let Some(current) = bounding_box {
+			let near_edge = current.top < ESCAPE_MARGIN || current.left < ESCAPE_MARGIN || current.bottom >= UNIVERSE_SIZE - ESCAPE_MARGIN || current.right >= UNIVERSE_SIZE - ESCAPE_MARGIN;
+			if near_edge || engine.generation_number() % ESCAPE_CHECK_INTERVAL == 0 {
+				let population = engine.population();
+				remove_escaped_spaceships(engine, &mut escaped, &mut identified);
+				escaped_population += population - engine.population();
+				bounding_box = engine.bounding_box();
+			}
+		}
+		if let Some(bounding_box) = bounding_box {
+			if bounding_box.width() * bounding_box.height() > max_bounding_box.0 * max_bounding_box.1 {
+				max_bounding_box = (bounding_box.width(), bounding_box.height());
+			}
+		}
+
+		populations.push(engine.population() + escaped_population);
+		if let Some(period) = population_period(&populations, step) {
+			return Outcome {
+				lifespan: first_generation + settled_since(&populations, period) as i32,
+				period: Some(period as u32),
+				max_bounding_box,
+				escaped
+			};
+		}
+	}
+
+	Outcome {
+		lifespan: engine.generation_number(),
+		period: None,
+		max_bounding_box,
+		escaped
+	}
+}
@@ -0,0 +1,78 @@
+extern crate sdl2;
+
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+
+use sdl2::rect::Point;
+use sdl2::rect::Rect;
+
+use crate::engine::BoundingBox;
+
+
+// Statistics of a single generation
+pub struct Record {
+	pub generation: i32,
+	pub population: u64,
+	pub births: u64,
+	pub deaths: u64,
+	pub bounding_box: Option<BoundingBox>,
+	pub live_chunks: u32
+}
+
+// History of the statistics of every generation of the current run
+pub struct Statistics {
+	records: Vec<Record>
+}
+
+impl Statistics {
+	pub fn new() -> Self {
+		Self {
+			records: Vec::new()
+		}
+	}
+
+	pub fn clear(&mut self) {
+		self.records.clear();
+	}
+
+	pub fn push(&mut self, record: Record) {
+		self.records.push(record);
+	}
+
+	pub fn last(&self) -> Option<&Record> {
+		self.records.last()
+	}
+
+	pub fn export_csv(&self, path: &str) -> std::io::Result<()> {
+		let mut writer = BufWriter::new(File::create(path)?);
+		writeln!(writer, "generation,population,births,deaths,bounding_box_width,bounding_box_height,live_chunks")?;
+		for record in &self.records {
+			let (width, height) = record.bounding_box.map_or((0, 0), |bounding_box| (bounding_box.width(), bounding_box.height()));
+			writeln!(writer, "{},{},{},{},{},{},{}", record.generation, record.population, record.births, record.deaths, width, height, record.live_chunks)?;
+		}
+		writer.flush()
+	}
+
+	// Scrolling population graph, one pixel column per generation. The vertical scale fits the highest visible population
+	pub fn draw_population_graph(&self, canvas: &mut sdl2::render::Canvas<sdl2::video::Window>, rect: Rect) {
+		canvas.set_draw_color(crate::COLOR_BLACK_1);
+		let _ = canvas.fill_rect(rect);
+
+		let visible_records = &self.records[self.records.len().saturating_sub(rect.width() as usize)..];
+		let max_population = visible_records.iter().map(|record| record.population).max().unwrap_or(0);
+		if max_population > 0 {
+			let points: Vec<Point> = visible_records.iter().enumerate().map(|(index, record)| {
+				let height = (record.population as f64 / max_population as f64 * (rect.height() - 1) as f64) as i32;
+				Point::new(rect.x() + index as i32, rect.bottom() - 1 - height)
+			}).collect();
+
+			canvas.set_draw_color(crate::COLOR_GREEN);
+			let _ = canvas.draw_lines(points.as_slice());
+		}
+
+		canvas.set_draw_color(crate::COLOR_BLACK_3);
+		let _ = canvas.draw_rect(rect);
+	}
+}
@@ -0,0 +1,220 @@
+use core::fmt;
+use std::time::SystemTime;
+
+use rand::Rng;
+use rand::SeedableRng;
+use rand::seq::index;
+use rand_chacha::ChaCha8Rng;
+
+use crate::engine::BoundingBox;
+use crate::rule::Rule;
+
+
+pub const USAGE: &str = "Randomness:
+  --probabilities <B3=0.9/S2=0.5,3=0.99>   Chance that each birth and survival of the rule happens, by amount of neighbors
+  --noise <fraction>                       Fraction of the cells in the bounding box of the pattern flipped at random each
+                                           generation, cells outside of it are never flipped
+  --seed <seed>                            Seed of the random draws, the same one repeats the same run";
+
+
+// Probabilistic variant of the rule plus noise. Every draw depends only on the seed, the generation and the cell, so runs with
+// the same seed are identical
+#[derive(Clone)]
+pub struct Stochastic {
+	pub birth: [f64; 9], // Chance that a birth the rule calls for happens, by amount of alive neighbors
+	pub survival: [f64; 9],
+	pub noise: f64,
+	pub seed: String,
+	seed_hash: u64
+}
+
+impl fmt::Display for Stochastic {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let probabilities = |chances: &[f64; 9]| chances.iter().enumerate()
+			.filter(|(_, chance)| **chance < 1.0)
+			.map(|(count, chance)| format!("{}={}", count, chance))
+			.collect::<Vec<String>>().join(",");
+		write!(f, "B{}/S{}, noise {}, seed \"{}\"", probabilities(&self.birth), probabilities(&self.survival), self.noise, self.seed)
+	}
+}
+
+impl Stochastic {
+	pub fn new(seed: &str) -> Self {
+		Self {
+			birth: [1.0; 9],
+			survival: [1.0; 9],
+			noise: 0.0,
+			seed: seed.to_string(),
+			seed_hash: hash_name(seed)
+		}
+	}
+
+	// Takes the randomness options out of the arguments, returning the rest of them. Without probabilities or noise the run is
+	// deterministic and there's nothing to return
+	pub fn parse_args(args: &[String]) -> Result<(Option<Self>, Vec<String>), String> {
+		let mut stochastic = Stochastic::new(SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs()).to_string().as_str());
+		let mut random = false;
+		let mut remaining = Vec::new();
+
+		let mut args = args.iter();
+		while let Some(arg) = args.next() {
+			match arg.as_str() {
+				"--probabilities" => {
+					stochastic.parse_probabilities(args.next().ok_or(format!("missing value for --probabilities\n{}", USAGE))?)?;
+					random = true;
+				},
+				"--noise" => {
+					let value = args.next().ok_or(format!("missing value for --noise\n{}", USAGE))?;
+					stochastic.noise = value.parse::<f64>().ok().filter(|noise| (0.0..=1.0).contains(noise)).ok_or(format!("invalid noise \"{}\", expected a fraction between 0 and 1", value))?;
+					random = true;
+				},
+				"--seed" => {
+					let seed = args.next().ok_or(format!("missing value for --seed\n{}", USAGE))?;
+					stochastic.seed = seed.to_string();
+					stochastic.seed_hash = hash_name(seed);
+				},
+				_ => remaining.push(arg.to_string())
+			}
+		}
+
+		Ok((if random { Some(stochastic) } else { None }, remaining))
+	}
+
+	// Like B3=0.9/S2=0.5,3=0.99, the counts that aren't given always happen. Several counts can share a chance (S23=0.9)
+	fn parse_probabilities(&mut self, text: &str) -> Result<(), String> {
+		for part in text.split('/') {
+			let (chances, entries) = match part.chars().next().map(|letter| letter.to_ascii_uppercase()) {
+				Some('B') => (&mut self.birth, &part[1..]),
+				Some('S') => (&mut self.survival, &part[1..]),
+				_ => return Err(format!("invalid probabilities \"{}\", expected B<counts>=<chance>/S<counts>=<chance>", text))
+			};
+			for entry in entries.split(',').filter(|entry| !entry.is_empty()) {
+				let (counts, chance) = entry.split_once('=').ok_or(format!("missing chance in \"{}\"", entry))?;
+				let chance = chance.parse::<f64>().ok().filter(|chance| (0.0..=1.0).contains(chance)).ok_or(format!("invalid chance \"{}\", expected a number between 0 and 1", chance))?;
+				for count in counts.chars() {
+					match count.to_digit(10) {
+						Some(count) if count <= 8 => chances[count as usize] = chance,
+						_ => return Err(format!("invalid neighbor count '{}'", count))
+					}
+				}
+			}
+		}
+		Ok(())
+	}
+
+	// Cells that are stable under the rule may still change, so chunks with alive cells can't be skipped
+	pub fn needs_every_chunk(&self) -> bool {
+		self.birth.iter().chain(self.survival.iter()).any(|chance| *chance < 1.0)
+	}
+
+	// Probabilities are given by amount of neighbors, rule tables, Larger than Life and block rules don't count them. The noise
+	// applies to any rule
+	pub fn supports(&self, rule: &Rule) -> Result<(), String> {
+		if self.needs_every_chunk() && (rule.table.is_some() || rule.range.is_some() || rule.block.is_some()) {
+			return Err(format!("{} doesn't decide births and survivals by amount of neighbors, it can't have probabilities", rule));
+		}
+		Ok(())
+	}
+
+	// Number in [0, 1) for a cell in a generation, from SplitMix64
+	pub fn draw(&self, generation: i32, row: usize, col: usize) -> f64 {
+		let mut value = self.seed_hash ^ (generation as u64).wrapping_mul(0x9e3779b97f4a7c15) ^ ((row as u64) << 32 | col as u64).wrapping_mul(0xbf58476d1ce4e5b9);
+		value = (value ^ (value >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+		value = (value ^ (value >> 27)).wrapping_mul(0x94d049bb133111eb);
+		value ^= value >> 31;
+		(value >> 11) as f64 / (1u64 << 53) as f64
+	}
+
+	// The state the rule gave a cell, undoing its birth or survival when the draw of the cell misses its chance. A cell that fails
+	// to survive dies as it would have, into the first dying state of a Generations rule
+	pub fn next(&self, rule: &Rule, state: u8, next_state: u8, neighbors: u32, draw: f64) -> u8 {
+		let born = state == 0 && rule.alive(next_state);
+		let survives = rule.alive(state) && next_state == state;
+		let chance = match (born, survives) {
+			(true, _) => self.birth[neighbors as usize],
+			(_, true) => self.survival[neighbors as usize],
+			_ => return next_state
+		};
+		if draw < chance {
+			return next_state;
+		}
+		match born || rule.species > 1 || rule.states == 2 {
+			true => 0,
+			false => 2
+		}
+	}
+
+	// Distinct cells of the region (the bounding box of the pattern) to flip after a generation, noise times all of them on average.
+	// Flipping cells of the whole matrix would grow soups far away from the pattern being studied
+	pub fn noise_cells(&self, generation: i32, region: BoundingBox) -> Vec<(usize, usize)> {
+		if self.noise == 0.0 {
+			return Vec::new();
+		}
+		let mut rng = ChaCha8Rng::seed_from_u64(self.seed_hash ^ (generation as u64).wrapping_mul(0x9e3779b97f4a7c15));
+		let (width, height) = (region.width() as usize, region.height() as usize);
+		let expected = self.noise * (width * height) as f64;
+		let amount = usize::min(expected.floor() as usize + rng.gen_bool(expected.fract()) as usize, width * height);
+		index::sample(&mut rng, width * height, amount).iter()
+			.map(|index| (region.top as usize + index / width, region.left as usize + index % width))
+			.collect()
+	}
+}
+
+// FNV-1a, so the same name always gives the same soup or the same draws regardless of the platform or Rust version
+pub fn hash_name(name: &str) -> u64 {
+	name.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_probabilities() {
+		let mut stochastic = Stochastic::new("seed");
+		stochastic.parse_probabilities("B3=0.9/S2=0.5,3=0.99").unwrap();
+		assert_eq!(stochastic.birth, [1.0, 1.0, 1.0, 0.9, 1.0, 1.0, 1.0, 1.0, 1.0]);
+		assert_eq!(stochastic.survival, [1.0, 1.0, 0.5, 0.99, 1.0, 1.0, 1.0, 1.0, 1.0]);
+
+		let mut stochastic = Stochastic::new("seed");
+		stochastic.parse_probabilities("s23=0.25").unwrap();
+		assert_eq!((stochastic.survival[2], stochastic.survival[3]), (0.25, 0.25));
+		assert!(stochastic.birth.iter().all(|chance| *chance == 1.0));
+	}
+
+	#[test]
+	fn parse_invalid_probabilities() {
+		for text in ["3=0.9", "B3", "B3=1.5", "B9=0.5", "S2=x"] {
+			assert!(Stochastic::new("seed").parse_probabilities(text).is_err(), "{} should be rejected", text);
+		}
+	}
+
+	#[test]
+	fn noise_cells_are_distinct() {
+		let mut stochastic = Stochastic::new("seed");
+		stochastic.noise = 0.9;
+		let region = BoundingBox { top: 10, left: 20, bottom: 13, right: 24 };
+		let mut cells = stochastic.noise_cells(0, region);
+		assert!(cells.iter().all(|(row, col)| (10..=13).contains(row) && (20..=24).contains(col)));
+		let amount = cells.len();
+		cells.sort();
+		cells.dedup();
+		assert_eq!(cells.len(), amount);
+		assert_eq!(stochastic.noise_cells(0, region), stochastic.noise_cells(0, region));
+	}
+
+	#[test]
+	fn probabilities_need_neighbor_counts() {
+		let mut stochastic = Stochastic::new("seed");
+		stochastic.noise = 0.1;
+		for text in ["B3/S23", "B3/S23/3", "QuadLife", "R2,C0,M1,S2..3,B3..3,NM", "Critters"] {
+			assert!(stochastic.supports(&Rule::parse(text).unwrap()).is_ok(), "noise should apply to {}", text);
+		}
+		stochastic.parse_probabilities("B3=0.5").unwrap();
+		assert!(stochastic.supports(&Rule::parse("B36/S23H").unwrap()).is_ok());
+		for text in ["R2,C0,M1,S2..3,B3..3,NM", "Critters", "BBM"] {
+			assert!(stochastic.supports(&Rule::parse(text).unwrap()).is_err(), "{} should be rejected", text);
+		}
+	}
+}
@@ -0,0 +1,121 @@
+use core::fmt;
+
+use crate::classification::Classification;
+use crate::engine::Engine;
+
+
+pub const USAGE: &str = "Stop conditions:
+  --stop-generation <amount>   Stop once the generation is reached
+  --stop-population <amount>   Stop when the population crosses the amount (in either direction)
+  --no-stop-extinction         Keep running when the population dies out
+  --no-stop-stable             Keep running when the pattern becomes a still life, oscillator or spaceship
+  --no-stop-edge               Keep running when the pattern reaches the edge of the matrix";
+
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum StopReason {
+	Extinct,
+	Stabilised(Classification),
+	PopulationCrossed(u64),
+	GenerationReached(i32),
+	EdgeReached
+}
+
+impl fmt::Display for StopReason {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			StopReason::Extinct => write!(f, "The population died out"),
+			StopReason::Stabilised(classification) => write!(f, "Stabilised into: {}", classification),
+			StopReason::PopulationCrossed(amount) => write!(f, "The population crossed {}", amount),
+			StopReason::GenerationReached(generation) => write!(f, "Generation {} reached", generation),
+			StopReason::EdgeReached => write!(f, "The pattern reached the edge of the matrix")
+		}
+	}
+}
+
+
+#[derive(Clone, Copy)]
+pub struct StopConditions {
+	pub extinction: bool,
+	pub stabilisation: bool,
+	pub edge: bool,
+	pub population: Option<u64>,
+	pub generation: Option<i32>
+}
+
+impl StopConditions {
+	pub fn new() -> Self {
+		Self {
+			extinction: true,
+			stabilisation: true,
+			edge: true,
+			population: None,
+			generation: None
+		}
+	}
+
+	// Takes the stop condition options out of the arguments, returning the rest of them
+	pub fn parse_args(args: &[String]) -> Result<(Self, Vec<String>), String> {
+		let mut conditions = StopConditions::new();
+		let mut remaining = Vec::new();
+
+		let mut args = args.iter();
+		while let Some(arg) = args.next() {
+			match arg.as_str() {
+				"--stop-generation" => {
+					let value = args.next().ok_or(format!("missing value for --stop-generation\n{}", USAGE))?;
+					conditions.generation = Some(value.parse::<i32>().map_err(|_| format!("invalid generation \"{}\"", value))?);
+				},
+				"--stop-population" => {
+					let value = args.next().ok_or(format!("missing value for --stop-population\n{}", USAGE))?;
+					conditions.population = Some(value.parse::<u64>().map_err(|_| format!("invalid population \"{}\"", value))?);
+				},
+				"--no-stop-extinction" => conditions.extinction = false,
+				"--no-stop-stable" => conditions.stabilisation = false,
+				"--no-stop-edge" => conditions.edge = false,
+				_ => remaining.push(arg.to_string())
+			}
+		}
+
+		Ok((conditions, remaining))
+	}
+
+	// Checks the engine after an iteration. The previous population is needed to know if the threshold was crossed
+	pub fn check(&self, engine: &Engine, classification: Option<(Classification, i32)>, previous_population: u64) -> Option<StopReason> {
+		let population = engine.population();
+
+		if self.extinction && population == 0 {
+			return Some(StopReason::Extinct);
+		}
+
+		if self.stabilisation {
+			if let Some((classification, _)) = classification {
+				if classification != Classification::Extinct {
+					return Some(StopReason::Stabilised(classification));
+				}
+			}
+		}
+
+		if let Some(threshold) = self.population {
+			if (previous_population < threshold && population >= threshold) || (previous_population > threshold && population <= threshold) {
+				return Some(StopReason::PopulationCrossed(threshold));
+			}
+		}
+
+		if let Some(generation) = self.generation {
+			if engine.generation_number() >= generation {
+				return Some(StopReason::GenerationReached(generation));
+			}
+		}
+
+		if self.edge {
+			if let Some(bounding_box) = engine.bounding_box() {
+				if bounding_box.top == 0 || bounding_box.left == 0 || bounding_box.bottom == engine.size() - 1 || bounding_box.right == engine.size() - 1 {
+					return Some(StopReason::EdgeReached);
+				}
+			}
+		}
+
+		None
+	}
+}
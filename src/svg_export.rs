@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::fs;
+
+use sdl2::pixels::Color;
+
+use crate::census;
+use crate::engine::BoundingBox;
+use crate::engine::Engine;
+
+
+const CELL_SIZE: u32 = 10; // Size of a cell at 100% zoom, the image can be scaled to any size
+const LABEL_MARGIN: u32 = 2; // Cells above and around the region where the labels fit
+const LABEL_SIZE: f32 = 1.4; // Height of the labels, in cells
+
+
+fn hex(color: Color) -> String {
+	format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+fn escape(text: &str) -> String {
+	text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// Name of each object of the region (or its apgcode if it has none), placed over its top left corner. Objects that couldn't be
+// identified, like the parts of a reaction, aren't labelled
+fn labels(engine: &Engine, region: BoundingBox) -> Vec<(u32, u32, String)> {
+	let rule = engine.rule();
+	let mut identified: HashMap<Vec<(u32, u32, u8)>, String> = HashMap::new();
+	census::separate(&engine.state_cells(Some(region))).iter().filter_map(|object| {
+		let top = object.iter().map(|cell| cell.0).min().unwrap_or(0);
+		let left = object.iter().map(|cell| cell.1).min().unwrap_or(0);
+		// Equal shapes are only identified once
+		let label = identified.entry(census::normalize(object)).or_insert_with(|| {
+			let apgcode = census::apgcode(object, rule);
+			match rule.is_life().then(|| census::known_name(apgcode.as_str())).flatten() {
+				Some(name) => name.to_string(),
+				None => apgcode
+			}
+		}).clone();
+		(label != census::UNKNOWN_APGCODE).then(|| (top - region.top, left - region.left, label))
+	}).collect()
+}
+
+// Vector image of the region, one cell per unit: the cells of each state as a single path of horizontal runs, and optionally the
+// grid lines and the name of each object
+pub fn to_svg(engine: &Engine, region: BoundingBox, grid: bool, with_labels: bool) -> String {
+	let margin = if with_labels { LABEL_MARGIN } else { 0 };
+	let (width, height) = (region.width() + margin * 2, region.height() + margin * 2);
+	let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"-{} -{} {} {}\" shape-rendering=\"crispEdges\">\n",
+		width * CELL_SIZE, height * CELL_SIZE, margin, margin, width, height);
+	svg += format!("<rect x=\"-{}\" y=\"-{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n", margin, margin, width, height, hex(Color::BLACK)).as_str();
+
+	let states = engine.rule().states;
+	for state in 1..states {
+		let in_state = |row: u32, col: u32| engine.get_state((region.top + row) as usize, (region.left + col) as usize) == state;
+		let mut path = String::new();
+		for row in 0..region.height() {
+			let mut col = 0;
+			while col < region.width() {
+				if !in_state(row, col) {
+					col += 1;
+					continue;
+				}
+				let start = col;
+				while col < region.width() && in_state(row, col) {
+					col += 1;
+				}
+				path += format!("M{} {}h{}v1h-{}z", start, row, col - start, col - start).as_str();
+			}
+		}
+		// Dying states (or those of a rule table) without cells are left out
+		if state == 1 || !path.is_empty() {
+			svg += format!("<path d=\"{}\" fill=\"{}\"/>\n", path, hex(crate::state_color(state, engine.rule()))).as_str();
+		}
+	}
+
+	if grid {
+		let mut lines = String::new();
+		for col in 1..region.width() {
+			lines += format!("M{} 0v{}", col, region.height()).as_str();
+		}
+		for row in 1..region.height() {
+			lines += format!("M0 {}h{}", row, region.width()).as_str();
+		}
+		svg += format!("<path d=\"{}\" stroke=\"{}\" stroke-width=\"0.1\" fill=\"none\"/>\n", lines, hex(crate::COLOR_BLACK_2)).as_str();
+		svg += format!("<rect width=\"{}\" height=\"{}\" stroke=\"{}\" stroke-width=\"0.1\" fill=\"none\"/>\n", region.width(), region.height(), hex(crate::COLOR_BLACK_3)).as_str();
+	}
+
+	if with_labels {
+		for (row, col, label) in labels(engine, region) {
+			svg += format!("<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"{}\" fill=\"{}\">{}</text>\n",
+				col, row as f32 - 0.3, LABEL_SIZE, hex(crate::COLOR_YELLOW), escape(label.as_str())).as_str();
+		}
+	}
+
+	svg += "</svg>\n";
+	svg
+}
+
+// Saves the region, or the whole pattern if there is none
+pub fn save(engine: &Engine, region: Option<BoundingBox>, path: &str, grid: bool, labels: bool) -> Result<(), String> {
+	let region = region.or(engine.bounding_box()).ok_or("the universe is empty".to_string())?;
+	fs::write(path, to_svg(engine, region, grid, labels)).map_err(|error| format!("could not write {}: {}", path, error))
+}